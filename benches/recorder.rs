@@ -0,0 +1,100 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Small {
+    requests: Counter,
+    errors: Counter,
+    connections: Gauge,
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Large {
+    requests: Counter,
+    regions: [Region; 8],
+}
+
+#[derive(Catalogue)]
+struct Region {
+    nodes: [Node; 16],
+}
+
+#[derive(Catalogue)]
+struct Node {
+    requests: Counter,
+    errors: Counter,
+    bytes: Counter,
+    connections: Gauge,
+    busy: DiscreteGauge,
+    latency: Histogram<60>,
+}
+
+static SMALL: Small = Small::new();
+static LARGE: Large = Large::new();
+
+/// Recording a sample should take the same time regardless of the size of the catalogue & depth of the key
+fn increment_counter(c: &mut Criterion) {
+    let mut group = c.benchmark_group("increment_counter");
+    let keys = [
+        ("small/first", &SMALL as &dyn Recorder, small::REQUESTS),
+        ("small/last", &SMALL, small::ERRORS),
+        ("large/first", &LARGE, large::REQUESTS),
+        ("large/last", &LARGE, large::regions::nodes::REQUESTS[7][15]),
+    ];
+    for (name, recorder, key) in keys {
+        let key = Key::from_name(key);
+        group.bench_function(name, |b| {
+            b.iter(|| recorder.increment_counter(black_box(&key), 1))
+        });
+    }
+    group.finish();
+}
+
+fn update_gauge(c: &mut Criterion) {
+    let mut group = c.benchmark_group("update_gauge");
+    let keys = [
+        ("small", &SMALL as &dyn Recorder, small::CONNECTIONS),
+        ("large", &LARGE, large::regions::nodes::BUSY[7][15]),
+    ];
+    for (name, recorder, key) in keys {
+        let key = Key::from_name(key);
+        group.bench_function(name, |b| {
+            b.iter(|| recorder.update_gauge(black_box(&key), GaugeValue::Increment(1.0)))
+        });
+    }
+    group.finish();
+}
+
+/// Samples of keys unknown to the catalogue should be dropped as fast as recorded, missing the key map
+fn unknown_key(c: &mut Criterion) {
+    let mut group = c.benchmark_group("unknown_key");
+    let keys = [
+        ("small", &SMALL as &dyn Recorder, "small.unknown"),
+        ("large/prefix", &LARGE, "unknown.requests"),
+        ("large/last", &LARGE, "large.regions.7.nodes.15.unknown"),
+    ];
+    for (name, recorder, key) in keys {
+        let key = Key::from_name(key);
+        group.bench_function(name, |b| {
+            b.iter(|| recorder.increment_counter(black_box(&key), 1))
+        });
+    }
+    group.finish();
+}
+
+/// Accessors skip both the `metrics` facade & the look-up of the key
+fn accessor(c: &mut Criterion) {
+    let mut group = c.benchmark_group("accessor");
+    group.bench_function("small", |b| {
+        b.iter(|| small::requests(black_box(&SMALL)).increment(1))
+    });
+    group.bench_function("large", |b| {
+        b.iter(|| large::regions::nodes::requests(black_box(&LARGE), 7, 15).increment(1))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, increment_counter, update_gauge, unknown_key, accessor);
+criterion_main!(benches);
@@ -1,48 +1,173 @@
-use crate::{ROOT_MARKER, SEPARATOR_MARKER, SKIP_MARKER};
-use proc_macro2::Ident;
-use quote::ToTokens;
+use crate::naming::check_name;
+use crate::{
+    DESCRIPTION_MARKER, INDEX_LABEL_MARKER, INSTANCE_LABEL_MARKER, KIND_MARKER, LABEL_MARKER,
+    ROOT_MARKER, ROUTE_MARKER, SEPARATOR_MARKER, SKIP_MARKER, STRICT_MARKER, UNIT_MARKER,
+};
+use inflector::Inflector;
+use proc_macro2::{Ident, TokenStream};
+use quote::{quote, ToTokens};
 use syn::{
-    Attribute, DataStruct, DeriveInput, Fields, Lit, Meta, NestedMeta, Path, PathSegment, Result,
-    Type,
+    Attribute, DataStruct, DeriveInput, Error, Fields, Lit, LitStr, Meta, MetaList, MetaNameValue,
+    NestedMeta, Path, PathSegment, Result, Type,
 };
 
 pub struct Field<'a> {
     pub original: &'a syn::Field,
+    /// Position of the field within its struct
+    pub index: usize,
     pub ty: &'a Type,
     pub attributes: Attributes,
 }
 
 impl<'a> Field<'a> {
     pub fn multiple_from_syn(fields: &'a Fields) -> Result<Vec<Self>> {
-        fields.iter().map(Field::from_syn).collect()
+        fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| Field::from_syn(field, index))
+            .collect()
     }
 
-    pub fn from_syn(node: &'a syn::Field) -> Result<Self> {
+    pub fn from_syn(node: &'a syn::Field, index: usize) -> Result<Self> {
         Ok(Field {
             original: node,
+            index,
             ty: &node.ty,
-            attributes: Attributes::from_node(&node.attrs),
+            attributes: Attributes::from_node(&node.attrs)?,
         })
     }
 
     pub fn get_metric(&self) -> Option<String> {
         match &self.attributes {
             Attributes::Root(root) => root.name_override.clone(),
-            Attributes::Struct(StructAttributes {
-                hidden,
-                name_override,
-            }) => {
-                if *hidden {
+            Attributes::Struct(attributes) => {
+                if attributes.hidden {
                     return None;
                 }
-                if let Some(name) = &name_override {
-                    Some(name.clone())
+                if let Some(name) = &attributes.name_override {
+                    Some(name.value())
+                } else if let Some(ident) = &self.original.ident {
+                    Some(ident.to_string())
                 } else {
-                    self.original.ident.as_ref().map(|x| x.to_string())
+                    // Fields of tuple structs are named after their position by default
+                    Some(format!("_{}", self.index))
                 }
             }
         }
     }
+
+    /// Member of the field within its struct, i.e. its name or its position within a tuple struct
+    pub fn member(&self) -> String {
+        match &self.original.ident {
+            Some(ident) => ident.to_string(),
+            None => self.index.to_string(),
+        }
+    }
+
+    /// Tokens declaring the name of the field, i.e. its name override or the field itself
+    pub fn name_tokens(&self) -> TokenStream {
+        match &self.attributes {
+            Attributes::Struct(attributes) => match &attributes.name_override {
+                Some(name) => name.to_token_stream(),
+                None => self.original.to_token_stream(),
+            },
+            Attributes::Root(_) => self.original.to_token_stream(),
+        }
+    }
+
+    /// Whether the field has an explicit name
+    pub fn has_name_override(&self) -> bool {
+        matches!(&self.attributes, Attributes::Struct(attributes) if attributes.name_override.is_some())
+    }
+
+    /// Explicit description of the metric, or its doc comment otherwise
+    pub fn get_description(&self) -> Option<String> {
+        match &self.attributes {
+            Attributes::Root(_) => None,
+            Attributes::Struct(attributes) => attributes.description.clone(),
+        }
+    }
+
+    /// Static labels applied to the metric, or all metrics of a sub-scope
+    pub fn get_labels(&self) -> Vec<(String, String)> {
+        match &self.attributes {
+            Attributes::Root(_) => vec![],
+            Attributes::Struct(attributes) => label_values(&attributes.labels),
+        }
+    }
+
+    /// Label distinguishing the instances of a sub-scope rendered as a shared family
+    pub fn get_instance_label(&self) -> Option<&AttributeValue> {
+        match &self.attributes {
+            Attributes::Root(_) => None,
+            Attributes::Struct(attributes) => attributes.instance_label.as_ref(),
+        }
+    }
+
+    /// Label distinguishing the elements of an array of metrics or sub-scopes
+    pub fn get_index_label(&self) -> Option<&AttributeValue> {
+        match &self.attributes {
+            Attributes::Root(_) => None,
+            Attributes::Struct(attributes) => attributes.index_label.as_ref(),
+        }
+    }
+
+    /// Label values selecting the sub-scope when recording labelled keys
+    pub fn get_route(&self) -> Vec<(String, String)> {
+        match &self.attributes {
+            Attributes::Root(_) => vec![],
+            Attributes::Struct(attributes) => label_values(&attributes.route),
+        }
+    }
+
+    /// Tokens declaring the route of the sub-scope, if any
+    pub fn route_tokens(&self) -> Option<TokenStream> {
+        match &self.attributes {
+            Attributes::Struct(attributes) if !attributes.route.is_empty() => {
+                let route = attributes
+                    .route
+                    .iter()
+                    .map(|(key, value)| quote! { #key = #value });
+                Some(quote! { #(#route),* })
+            }
+            _ => None,
+        }
+    }
+
+    /// Names of all labels declared on the field, along with the tokens declaring them
+    pub fn label_names(&self) -> Vec<(String, TokenStream)> {
+        match &self.attributes {
+            Attributes::Root(_) => vec![],
+            Attributes::Struct(attributes) => {
+                let keys = attributes
+                    .labels
+                    .iter()
+                    .chain(&attributes.route)
+                    .map(|(key, _)| (key.to_string(), key.to_token_stream()));
+                let labels = attributes
+                    .instance_label
+                    .iter()
+                    .chain(&attributes.index_label)
+                    .map(|label| (label.value(), label.lit.to_token_stream()));
+                keys.chain(labels).collect()
+            }
+        }
+    }
+
+    pub fn get_unit(&self) -> Option<&AttributeValue> {
+        match &self.attributes {
+            Attributes::Root(_) => None,
+            Attributes::Struct(attributes) => attributes.unit.as_ref(),
+        }
+    }
+
+    /// Explicit kind of the metric, for types which aren't recognised by their name
+    pub fn get_kind(&self) -> Option<&AttributeValue> {
+        match &self.attributes {
+            Attributes::Root(_) => None,
+            Attributes::Struct(attributes) => attributes.kind.as_ref(),
+        }
+    }
 }
 
 pub struct Struct<'a> {
@@ -58,27 +183,62 @@ impl<'a> Struct<'a> {
             _original: node,
             ident: node.ident.clone(),
             fields: Field::multiple_from_syn(&data.fields)?,
-            attributes: Attributes::from_node(&node.attrs),
+            attributes: Attributes::from_node(&node.attrs)?,
         })
     }
 }
 
-#[derive(Debug)]
 pub enum Attributes {
     Root(RootAttributes),
-    Struct(StructAttributes),
+    Struct(Box<StructAttributes>),
 }
 
-#[derive(Default, Debug)]
+#[derive(Default, Debug, Clone)]
 pub struct RootAttributes {
     pub separator: Option<String>,
     pub name_override: Option<String>,
+    /// Whether the recorder asserts that all keys resolve, in debug builds
+    pub strict: bool,
 }
 
-#[derive(Default, Debug)]
+#[derive(Default)]
 pub struct StructAttributes {
     pub hidden: bool,
-    pub name_override: Option<String>,
+    pub name_override: Option<LitStr>,
+    pub description: Option<String>,
+    pub unit: Option<AttributeValue>,
+    pub labels: Vec<(Ident, LitStr)>,
+    pub instance_label: Option<AttributeValue>,
+    pub route: Vec<(Ident, LitStr)>,
+    pub index_label: Option<AttributeValue>,
+    pub kind: Option<AttributeValue>,
+}
+
+/// String value of a `key = "value"` attribute, keeping its tokens to report errors
+#[derive(Clone)]
+pub struct AttributeValue {
+    pub path: Path,
+    pub lit: LitStr,
+}
+
+impl AttributeValue {
+    fn from_meta(val: &MetaNameValue, what: &str) -> Result<Self> {
+        Ok(Self {
+            path: val.path.clone(),
+            lit: lit_str(val, what)?,
+        })
+    }
+
+    pub fn value(&self) -> String {
+        self.lit.value()
+    }
+}
+
+impl ToTokens for AttributeValue {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let (path, lit) = (&self.path, &self.lit);
+        tokens.extend(quote! { #path = #lit });
+    }
 }
 
 impl Attributes {
@@ -89,61 +249,205 @@ impl Attributes {
         }
     }
 
-    pub fn from_node(attrs: &[Attribute]) -> Self {
+    pub fn from_node(attrs: &[Attribute]) -> Result<Self> {
         let mut root = None;
+        let mut separator = None;
+        let mut strict = None;
         let mut attributes = StructAttributes::default();
+        // First attribute which only applies to metrics & sub-scopes, if any
+        let mut struct_only: Option<Path> = None;
+        let mut doc = vec![];
         for attr in attrs {
-            if let Ok(meta) = attr.parse_meta() {
-                if let Some(ident) = meta.path().get_ident() {
-                    if ident == "metric" {
-                        match &meta {
-                            Meta::List(list) => {
-                                for nested in &list.nested {
-                                    match nested {
-                                        NestedMeta::Meta(m) => {
-                                            if m.path().is_ident(SKIP_MARKER) {
-                                                attributes.hidden = true;
-                                            }
-                                            if m.path().is_ident(ROOT_MARKER) {
-                                                root.get_or_insert(RootAttributes::default());
-                                            }
-                                            if let Meta::NameValue(val) = m {
-                                                if val.path.is_ident(SEPARATOR_MARKER) {
-                                                    if let Lit::Str(sep) = &val.lit {
-                                                        root.as_mut().expect("Separator cannot be specified on a non-root element").separator = Some(sep.value());
-                                                    } else {
-                                                        panic!("Separator should be specified as a string")
-                                                    }
-                                                }
-                                            }
-                                        }
-                                        NestedMeta::Lit(lit) => {
-                                            if let Lit::Str(name) = lit {
-                                                attributes.name_override = Some(name.value());
-                                                if let Some(root) = &mut root {
-                                                    root.name_override = Some(name.value());
-                                                }
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                            Meta::Path(_) => {}
-                            Meta::NameValue(_) => {}
+            if attr.path.is_ident("doc") {
+                if let Ok(Meta::NameValue(MetaNameValue {
+                    lit: Lit::Str(line),
+                    ..
+                })) = attr.parse_meta()
+                {
+                    doc.push(line.value().trim().to_string());
+                }
+                continue;
+            }
+            if !attr.path.is_ident("metric") {
+                continue;
+            }
+            let list = match attr.parse_meta()? {
+                Meta::List(list) => list,
+                meta => {
+                    return Err(Error::new_spanned(
+                        meta,
+                        "Expected a list of metric attributes, e.g. `#[metric(skip)]`",
+                    ))
+                }
+            };
+            for nested in &list.nested {
+                match nested {
+                    NestedMeta::Lit(Lit::Str(name)) => {
+                        attributes.name_override = Some(name.clone());
+                    }
+                    NestedMeta::Lit(lit) => {
+                        return Err(Error::new_spanned(
+                            lit,
+                            "A metric name should be specified as a string",
+                        ));
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident(SKIP_MARKER) => {
+                        attributes.hidden = true;
+                        struct_only.get_or_insert_with(|| path.clone());
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident(ROOT_MARKER) => {
+                        root.get_or_insert(RootAttributes::default());
+                    }
+                    NestedMeta::Meta(Meta::Path(path)) if path.is_ident(STRICT_MARKER) => {
+                        strict = Some(path.clone());
+                    }
+                    NestedMeta::Meta(Meta::NameValue(val)) => {
+                        if val.path.is_ident(SEPARATOR_MARKER) {
+                            separator = Some((string_value(val, "A separator")?, val.clone()));
+                        } else if val.path.is_ident(DESCRIPTION_MARKER) {
+                            attributes.description = Some(string_value(val, "A description")?);
+                        } else if val.path.is_ident(UNIT_MARKER) {
+                            attributes.unit = Some(AttributeValue::from_meta(val, "A unit")?);
+                        } else if val.path.is_ident(INSTANCE_LABEL_MARKER) {
+                            attributes.instance_label =
+                                Some(AttributeValue::from_meta(val, "An instance label")?);
+                        } else if val.path.is_ident(INDEX_LABEL_MARKER) {
+                            attributes.index_label =
+                                Some(AttributeValue::from_meta(val, "An index label")?);
+                        } else if val.path.is_ident(KIND_MARKER) {
+                            attributes.kind = Some(AttributeValue::from_meta(val, "A kind")?);
+                        } else {
+                            return Err(unknown_attribute(&val.path));
+                        }
+                        if !val.path.is_ident(SEPARATOR_MARKER) {
+                            struct_only.get_or_insert_with(|| val.path.clone());
                         }
                     }
+                    NestedMeta::Meta(Meta::List(list)) if list.path.is_ident(LABEL_MARKER) => {
+                        attributes.labels.extend(label_pairs(list)?);
+                        struct_only.get_or_insert_with(|| list.path.clone());
+                    }
+                    NestedMeta::Meta(Meta::List(list)) if list.path.is_ident(ROUTE_MARKER) => {
+                        attributes.route.extend(label_pairs(list)?);
+                        struct_only.get_or_insert_with(|| list.path.clone());
+                    }
+                    NestedMeta::Meta(meta) => return Err(unknown_attribute(meta.path())),
                 }
             }
         }
 
-        if let Some(root) = root {
-            Self::Root(root)
-        } else {
-            Self::Struct(attributes)
+        if attributes.description.is_none() {
+            let doc = doc
+                .into_iter()
+                .filter(|line| !line.is_empty())
+                .collect::<Vec<_>>()
+                .join(" ");
+            if !doc.is_empty() {
+                attributes.description = Some(doc);
+            }
+        }
+
+        if let (Some(_), Some(path)) = (&root, &struct_only) {
+            return Err(Error::new_spanned(
+                path,
+                format!(
+                    "The `{}` attribute can't be specified on a root",
+                    path.to_token_stream()
+                ),
+            ));
+        }
+        match (root, separator, strict) {
+            (Some(mut root), separator, strict) => {
+                root.separator = separator.map(|(separator, _)| separator);
+                // An empty root name omits the prefix of all keys
+                if let Some(name) = attributes.name_override.as_ref() {
+                    if !name.value().is_empty() {
+                        check_name(&name.value().to_snake_case(), name)?;
+                    }
+                }
+                root.name_override = attributes.name_override.map(|name| name.value());
+                root.strict = strict.is_some();
+                Ok(Self::Root(root))
+            }
+            (None, Some((_, val)), _) => Err(Error::new_spanned(
+                val,
+                "A separator can only be specified on a root",
+            )),
+            (None, None, Some(path)) => Err(Error::new_spanned(
+                path,
+                "Strict mode can only be specified on a root",
+            )),
+            (None, None, None) => Ok(Self::Struct(Box::new(attributes))),
         }
     }
 }
 
+/// Value of a `key = "value"` attribute
+fn string_value(val: &MetaNameValue, what: &str) -> Result<String> {
+    lit_str(val, what).map(|value| value.value())
+}
+
+/// String literal of a `key = "value"` attribute
+fn lit_str(val: &MetaNameValue, what: &str) -> Result<LitStr> {
+    match &val.lit {
+        Lit::Str(value) => Ok(value.clone()),
+        lit => Err(Error::new_spanned(
+            lit,
+            format!("{} should be specified as a string", what),
+        )),
+    }
+}
+
+fn label_values(labels: &[(Ident, LitStr)]) -> Vec<(String, String)> {
+    labels
+        .iter()
+        .map(|(key, value)| (key.to_string(), value.value()))
+        .collect()
+}
+
+fn unknown_attribute(path: &Path) -> Error {
+    let known = [
+        SKIP_MARKER,
+        ROOT_MARKER,
+        SEPARATOR_MARKER,
+        STRICT_MARKER,
+        DESCRIPTION_MARKER,
+        UNIT_MARKER,
+        LABEL_MARKER,
+        INSTANCE_LABEL_MARKER,
+        ROUTE_MARKER,
+        INDEX_LABEL_MARKER,
+        KIND_MARKER,
+    ];
+    Error::new_spanned(
+        path,
+        format!(
+            "Unknown metric attribute `{}`, expected one of {}",
+            path.to_token_stream().to_string().replace(' ', ""),
+            known.join(", ")
+        ),
+    )
+}
+
+/// Parses a list of labels, specified as `key = "value"`
+fn label_pairs(list: &MetaList) -> Result<Vec<(Ident, LitStr)>> {
+    list.nested
+        .iter()
+        .map(|label| match label {
+            NestedMeta::Meta(Meta::NameValue(val)) => {
+                let key = val.path.get_ident().ok_or_else(|| {
+                    Error::new_spanned(&val.path, "A label key should be an identifier")
+                })?;
+                Ok((key.clone(), lit_str(val, "A label value")?))
+            }
+            label => Err(Error::new_spanned(
+                label,
+                "Labels should be specified as `key = \"value\"`",
+            )),
+        })
+        .collect()
+}
+
 #[derive(Clone, Debug)]
 pub struct TypePath {
     pub path: String,
@@ -0,0 +1,126 @@
+use crate::ast::Attributes;
+use crate::naming::check_label;
+use crate::scope_composition::ScopeComposition;
+use inflector::Inflector;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+use syn::{Data, DataEnum, DeriveInput, Error, Fields, LitStr, Result};
+
+/// Key of a metric family: an enum of unit variants, each selecting a member of the family
+pub struct FamilyKey<'a> {
+    input: &'a DeriveInput,
+    label: String,
+    variants: Vec<(Ident, String)>,
+}
+
+impl<'a> FamilyKey<'a> {
+    pub fn from_syn(input: &'a DeriveInput) -> Result<Self> {
+        let data = match &input.data {
+            Data::Enum(data) => data,
+            Data::Struct(data) => {
+                return Err(Error::new_spanned(
+                    data.struct_token,
+                    "Family keys are only supported as enums",
+                ))
+            }
+            Data::Union(data) => {
+                return Err(Error::new_spanned(
+                    data.union_token,
+                    "Family keys are only supported as enums",
+                ))
+            }
+        };
+        if !input.generics.params.is_empty() {
+            return Err(Error::new_spanned(
+                &input.generics,
+                "Family keys cannot be generic",
+            ));
+        }
+        Ok(FamilyKey {
+            input,
+            label: family_label(input)?,
+            variants: family_variants(input, data)?,
+        })
+    }
+
+    pub fn generate(&self) -> TokenStream {
+        let ident = &self.input.ident;
+        let label = &self.label;
+        let len = self.variants.len();
+        let values = self.variants.iter().map(|(_, value)| value);
+        let indices = self
+            .variants
+            .iter()
+            .enumerate()
+            .map(|(index, (variant, _))| quote! { #ident::#variant => #index });
+        let composition = ScopeComposition::new(self.input.clone(), vec![]).start();
+
+        quote! {
+            impl ::metrics_catalogue::FamilyKey for #ident {
+                const LABEL: &'static str = #label;
+                const VALUES: &'static [&'static str] = &[#(#values),*];
+                type Array<M: ::metrics_catalogue::ConstDefault> = [M; #len];
+
+                fn index(&self) -> usize {
+                    match self {
+                        #(#indices),*
+                    }
+                }
+            }
+
+            #composition
+        }
+    }
+}
+
+/// Name of the label distinguishing the members of a family
+pub fn family_label(input: &DeriveInput) -> Result<String> {
+    match name_override(&Attributes::from_node(&input.attrs)?) {
+        Some(label) => {
+            check_label(&label.value(), &label)?;
+            Ok(label.value())
+        }
+        None => {
+            let label = input.ident.to_string().to_snake_case();
+            check_label(&label, &input.ident)?;
+            Ok(label)
+        }
+    }
+}
+
+/// Key constant & label value of each variant of a family key
+pub fn family_variants(input: &DeriveInput, data: &DataEnum) -> Result<Vec<(Ident, String)>> {
+    if data.variants.is_empty() {
+        return Err(Error::new_spanned(
+            input,
+            "Family keys should have at least one variant",
+        ));
+    }
+    data.variants
+        .iter()
+        .map(|variant| {
+            if !matches!(variant.fields, Fields::Unit) {
+                return Err(Error::new_spanned(
+                    variant,
+                    "Family keys should only have unit variants",
+                ));
+            }
+            let value = name_override(&Attributes::from_node(&variant.attrs)?)
+                .map(|value| value.value())
+                .unwrap_or_else(|| variant.ident.to_string().to_snake_case());
+            Ok((variant.ident.clone(), value))
+        })
+        .collect()
+}
+
+/// Name of the key constant of a variant
+pub fn variant_key(variant: &Ident) -> Ident {
+    format_ident!("{}", variant.to_string().to_screaming_snake_case())
+}
+
+fn name_override(attributes: &Attributes) -> Option<LitStr> {
+    match attributes {
+        Attributes::Struct(attributes) => attributes.name_override.clone(),
+        Attributes::Root(_) => None,
+    }
+}
@@ -1,27 +1,33 @@
 extern crate proc_macro;
 
-use crate::metric_tree::MetricTree;
+use crate::family_key::FamilyKey;
+use crate::metric_tree::parse_scope;
+use crate::scope_composition::ScopeComposition;
 use proc_macro::TokenStream;
 use quote::quote;
-use std::sync::Mutex;
 use syn::{parse_macro_input, DeriveInput, Result};
 
 mod ast;
+mod family_key;
 mod metric_scope;
 mod metric_tree;
+mod naming;
+mod scope_composition;
 mod scoped_catalogue;
 
 const SKIP_MARKER: &str = "skip";
 const ROOT_MARKER: &str = "root";
 const SEPARATOR_MARKER: &str = "separator";
+const STRICT_MARKER: &str = "strict";
+const DESCRIPTION_MARKER: &str = "description";
+const UNIT_MARKER: &str = "unit";
+const LABEL_MARKER: &str = "label";
+const INSTANCE_LABEL_MARKER: &str = "instance_label";
+const ROUTE_MARKER: &str = "route";
+const INDEX_LABEL_MARKER: &str = "index_label";
+const KIND_MARKER: &str = "kind";
 const DEFAULT_SEPARATOR: &str = ".";
-
-lazy_static::lazy_static! {
-    /// Hierarchical mapping of metric scopes
-    static ref METRIC_TREE: Mutex<MetricTree> = {
-        Mutex::new(MetricTree::default())
-    };
-}
+const DEFAULT_INDEX_LABEL: &str = "index";
 
 #[proc_macro_derive(Catalogue, attributes(metric))]
 pub fn derive_metrics(input: TokenStream) -> TokenStream {
@@ -29,13 +35,34 @@ pub fn derive_metrics(input: TokenStream) -> TokenStream {
     generate(input).unwrap_or_else(|err| err.to_compile_error().into())
 }
 
+#[proc_macro_derive(FamilyKey, attributes(metric))]
+pub fn derive_family_key(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    FamilyKey::from_syn(&input)
+        .map(|key| key.generate())
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
+/// Composes the description of a scope from its sub-scopes, as started by the `Catalogue` derive
+#[doc(hidden)]
+#[proc_macro]
+pub fn compose_scope(input: TokenStream) -> TokenStream {
+    let composition = parse_macro_input!(input as ScopeComposition);
+    composition
+        .generate()
+        .unwrap_or_else(|err| err.to_compile_error())
+        .into()
+}
+
 fn generate(input: DeriveInput) -> Result<TokenStream> {
-    let mut tree = METRIC_TREE.lock().unwrap();
-    tree.parse_struct(input)?;
-    Ok(if tree.is_complete() {
-        tree.generate()
-    } else {
-        quote! {}
+    let scope = parse_scope(&input)?;
+    let implementation = scope.generate();
+    let composition = ScopeComposition::new(input.clone(), scope.sub_scope_paths()).start();
+    Ok(quote! {
+        #implementation
+
+        #composition
     }
     .into())
 }
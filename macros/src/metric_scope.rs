@@ -1,34 +1,51 @@
-use crate::ast::TypePath;
+use crate::ast::{RootAttributes, TypePath};
+use crate::DEFAULT_SEPARATOR;
 use inflector::Inflector;
 use proc_macro2::Ident;
-use quote::{format_ident, quote};
-use std::collections::HashMap;
+use quote::{format_ident, quote, quote_spanned};
+use std::collections::{BTreeMap, HashMap};
 use std::convert::TryFrom;
 use std::fmt;
-use syn::{Error, Path, Result};
+use syn::spanned::Spanned;
+use syn::{Error, Generics, Index, Member, Path, Result};
 
-#[derive(Debug)]
 pub struct MetricScope {
     pub struct_name: String,
-    pub name_override: Option<String>,
+    pub generics: Generics,
+    pub root: Option<RootAttributes>,
     pub metrics: Vec<MetricInstance>,
     pub sub_metrics: HashMap<String, SubMetric>,
     pub other_fields: HashMap<String, String>,
+    /// Member of the registry recording the keys unknown to a root
+    pub dynamic: Option<String>,
+    /// Member counting the samples dropped for keys unknown to a root
+    pub unknown_keys: Option<String>,
 }
 
 impl MetricScope {
-    pub fn generate(&self, key_separator: &str, is_root: bool) -> proc_macro2::TokenStream {
+    pub fn generate(&self) -> proc_macro2::TokenStream {
         let initialize = self.generate_init();
-        let registry_trait = self.generate_registry_trait(key_separator, is_root);
+        let fields = self.generate_fields();
+        let scoped_registry = self.generate_scoped_registry();
+        // The registry of a root is generated along with its catalogue, once the keys of all its sub-scopes are known
+        let registry = if self.root.is_none() {
+            self.generate_registry(None)
+        } else {
+            quote! {}
+        };
         #[cfg(feature = "prometheus")]
-        let prometheus = self.generate_prometheus(key_separator, is_root);
+        let prometheus = self.generate_prometheus();
         #[cfg(not(feature = "prometheus"))]
         let prometheus = quote! {};
 
         quote! {
             #initialize
 
-            #registry_trait
+            #fields
+
+            #scoped_registry
+
+            #registry
 
             #prometheus
         }
@@ -36,117 +53,360 @@ impl MetricScope {
 
     fn generate_init(&self) -> proc_macro2::TokenStream {
         let struct_name = format_ident!("{}", &self.struct_name);
-        let metric_inits = self
-            .metrics
-            .iter()
-            .map(|f| (f.instance.clone(), f.type_path.full_path()))
-            .map(default_init);
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let metric_inits = self.metrics.iter().map(|f| {
+            if f.custom && f.array.is_none() {
+                const_default_init(&f.instance, &f.type_path.full_path())
+            } else {
+                array_init(&f.instance, &f.type_path.full_path(), f.array.as_ref())
+            }
+        });
         let other_inits = self.other_fields.iter().map(default_init);
-        let sub_metrics = self.sub_metrics.iter().map(default_init);
+        let sub_metrics = self
+            .sub_metrics
+            .values()
+            .map(|v| array_init(&v.member, &v.ident, v.array.as_ref()));
         let inits = metric_inits.chain(other_inits).chain(sub_metrics);
+        // Metrics declared using their kind are checked against their `CatalogueMetric` implementation
+        let kind_checks = self
+            .metrics
+            .iter()
+            .filter(|m| m.custom)
+            .map(|m| {
+                let ty = syn::parse_str::<Path>(&m.type_path.full_path())
+                    .unwrap_or_else(|_| panic!("invalid path: {}", m.type_path.full_path()));
+                let (kind, _) = m.metric_type.kind_tokens();
+                let message = format!("The declared kind of `{}` doesn't match its type", m.name);
+                // Reported on the type of the field, rather than on the derive
+                let span = m.original.ty.span();
+                quote_spanned! { span=>
+                    assert!(
+                        matches!(<#ty as ::metrics_catalogue::CatalogueMetric>::KIND, ::metrics_catalogue::MetricKind::#kind),
+                        #message
+                    );
+                }
+            })
+            .collect::<Vec<_>>();
+        let (kind_check, check_kinds) = if kind_checks.is_empty() {
+            (quote! {}, quote! {})
+        } else {
+            (
+                quote! {
+                    #[doc(hidden)]
+                    const __METRICS_CATALOGUE_KINDS: () = { #(#kind_checks)* };
+                },
+                quote! { let _: () = Self::__METRICS_CATALOGUE_KINDS; },
+            )
+        };
 
         quote! {
-            impl #struct_name {
+            impl #impl_generics #struct_name #ty_generics #where_clause {
+                #kind_check
+
                 pub const fn new() -> Self {
+                    #check_kinds
                     Self {
                         #(#inits),*
                     }
                 }
             }
+
+            impl #impl_generics ::metrics_catalogue::ConstDefault for #struct_name #ty_generics #where_clause {
+                const DEFAULT: Self = Self::new();
+            }
         }
     }
 
-    fn generate_registry_trait(
-        &self,
-        key_separator: &str,
-        is_root: bool,
-    ) -> proc_macro2::TokenStream {
+    /// Projections of the fields of the metrics & sub-scopes, by position
+    fn generate_fields(&self) -> proc_macro2::TokenStream {
         let struct_name = format_ident!("{}", &self.struct_name);
-        let counters = match_metric_names(&self.metrics, &[MetricType::Counter], None);
-        let sub_counters = self
-            .sub_metrics
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let metrics = self
+            .metrics
             .iter()
-            .filter(|(_, m)| !m.hidden)
-            .map(|(k, _v)| {
-                let sub = format_ident!("{}", k);
-                let prefix = format!("{}{}", k, key_separator);
-                quote! { .or_else(|| name.strip_prefix(#prefix).and_then(|n| ::metrics_catalogue::Registry::find_counter(&self.#sub, n))) }
-            });
+            .map(|m| (m.position, &m.instance, &m.original.ty));
+        let sub_metrics = self
+            .sub_metrics
+            .values()
+            .map(|v| (v.position, &v.member, &v.ty));
+        let fields = metrics.chain(sub_metrics).map(|(position, name, ty)| {
+            let field = member(name);
+            quote! {
+                impl #impl_generics ::metrics_catalogue::ScopeField<#position> for #struct_name #ty_generics #where_clause {
+                    type Field = #ty;
+
+                    #[inline]
+                    fn field(&self) -> &#ty {
+                        &self.#field
+                    }
+                }
+            }
+        });
+        quote! { #(#fields)* }
+    }
+
+    fn generate_scoped_registry(&self) -> proc_macro2::TokenStream {
+        let struct_name = format_ident!("{}", &self.struct_name);
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let counters =
+            match_metric_names(&self.metrics, &[MetricType::Counter], Some("CounterMetric"));
+        let routed_counters = self.match_routed_sub_metrics(quote! { route_scoped_counter });
+        let indexed_counters =
+            match_indexed_metrics(&self.metrics, &[MetricType::Counter], Some("CounterMetric"));
+        let sub_counters =
+            self.match_sub_metrics(quote! { route_scoped_counter }, quote! { labels, });
         let gauges = match_metric_names(
             &self.metrics,
             &[MetricType::Gauge, MetricType::DiscreteGauge],
             Some("GaugeMetric"),
         );
-        let sub_gauges = self
-            .sub_metrics
-            .iter()
-            .filter(|(_, m)| !m.hidden)
-            .map(|(k, _v)| {
-                let sub = format_ident!("{}", k);
-                let prefix = format!("{}{}", k, key_separator);
-                quote! { .or_else(|| name.strip_prefix(#prefix).and_then(|n| ::metrics_catalogue::Registry::find_gauge(&self.#sub, n))) }
-            });
-
+        let routed_gauges = self.match_routed_sub_metrics(quote! { route_scoped_gauge });
+        let indexed_gauges = match_indexed_metrics(
+            &self.metrics,
+            &[MetricType::Gauge, MetricType::DiscreteGauge],
+            Some("GaugeMetric"),
+        );
+        let sub_gauges = self.match_sub_metrics(quote! { route_scoped_gauge }, quote! { labels, });
         let histograms = match_metric_names(
             &self.metrics,
             &[MetricType::Histogram],
             Some("HistogramMetric"),
         );
-        let sub_histograms = self.sub_metrics.iter().filter(|(_, m)| !m.hidden).map(|(k, _v)| {
-            let sub = format_ident!("{}", k);
-            let prefix = format!("{}{}", k, key_separator);
-            quote! { .or_else(|| name.strip_prefix(#prefix).and_then(|n| ::metrics_catalogue::Registry::find_histogram(&self.#sub, n))) }
+        let routed_histograms = self.match_routed_sub_metrics(quote! { route_scoped_histogram });
+        let indexed_histograms = match_indexed_metrics(
+            &self.metrics,
+            &[MetricType::Histogram],
+            Some("HistogramMetric"),
+        );
+        let sub_histograms =
+            self.match_sub_metrics(quote! { route_scoped_histogram }, quote! { labels, });
+        let descriptions = self
+            .metrics
+            .iter()
+            .filter(|m| !m.hidden && m.array.is_none() && m.family.is_none())
+            .filter_map(|m| {
+                let name = &m.name;
+                m.description
+                    .as_ref()
+                    .map(|description| quote! { #name => Some(#description), })
+            });
+        let indexed_descriptions = self.metrics.iter().filter(|m| !m.hidden).filter_map(|m| {
+            let description = m.description.as_ref()?;
+            match_member(m, quote! { Some(#description) })
         });
+        let sub_descriptions =
+            self.match_sub_metrics(quote! { find_scoped_description }, quote! {});
+        let units = self
+            .metrics
+            .iter()
+            .filter(|m| !m.hidden && m.array.is_none() && m.family.is_none() && m.unit.is_some())
+            .map(|m| {
+                let name = &m.name;
+                let unit = m.unit_tokens();
+                quote! { #name => #unit, }
+            });
+        let indexed_units = self
+            .metrics
+            .iter()
+            .filter(|m| !m.hidden && m.unit.is_some())
+            .filter_map(|m| match_member(m, m.unit_tokens()));
+        let sub_units = self.match_sub_metrics(quote! { find_scoped_unit }, quote! {});
 
-        let with_strip_prefix = |segment| {
-            if is_root
-                && self
-                    .name_override
-                    .as_ref()
-                    .map(|x| !x.is_empty())
-                    .unwrap_or(true)
-            {
-                let prefix = format!(
-                    "{}{}",
-                    if is_root {
-                        self.mod_name()
-                    } else {
-                        self.struct_name.clone()
-                    },
-                    key_separator
-                );
+        let visit_metrics = self.visit_metrics();
+        let locate_metrics = self.locate_metrics();
+
+        let has_visible_members = self
+            .metrics
+            .iter()
+            .any(|m| !m.hidden && (m.array.is_some() || m.family.is_some()));
+        let (separator_ident, labels_ident) =
+            if self.has_visible_sub_metrics() || has_visible_members {
+                (quote! { separator }, quote! { labels })
+            } else {
+                (quote! { _separator }, quote! { _labels })
+            };
+
+        quote! {
+            impl #impl_generics ::metrics_catalogue::ScopedRegistry for #struct_name #ty_generics #where_clause {
+                fn route_scoped_counter(&self, name: &str, #labels_ident: &[::metrics_catalogue::Label], #separator_ident: &str) -> Option<&dyn ::metrics_catalogue::CounterMetric> {
+                    #(#routed_counters)*
+                    match name {
+                        #(#counters),*
+                    }
+                    #(#indexed_counters)*
+                    #(#sub_counters)*
+                }
+
+                fn route_scoped_gauge(&self, name: &str, #labels_ident: &[::metrics_catalogue::Label], #separator_ident: &str) -> Option<&dyn ::metrics_catalogue::GaugeMetric> {
+                    #(#routed_gauges)*
+                    match name {
+                        #(#gauges),*
+                    }
+                    #(#indexed_gauges)*
+                    #(#sub_gauges)*
+                }
+
+                fn route_scoped_histogram(&self, name: &str, #labels_ident: &[::metrics_catalogue::Label], #separator_ident: &str) -> Option<&dyn ::metrics_catalogue::HistogramMetric> {
+                    #(#routed_histograms)*
+                    match name {
+                        #(#histograms),*
+                    }
+                    #(#indexed_histograms)*
+                    #(#sub_histograms)*
+                }
+
+                fn find_scoped_description(&self, name: &str, #separator_ident: &str) -> Option<&'static str> {
+                    match name {
+                        #(#descriptions)*
+                        _ => None,
+                    }
+                    #(#indexed_descriptions)*
+                    #(#sub_descriptions)*
+                }
+
+                fn find_scoped_unit(&self, name: &str, #separator_ident: &str) -> Option<::metrics_catalogue::Unit> {
+                    match name {
+                        #(#units)*
+                        _ => None,
+                    }
+                    #(#indexed_units)*
+                    #(#sub_units)*
+                }
+
+                #[allow(unused_variables)]
+                fn visit_scoped(&self, prefix: &str, separator: &str, visitor: &mut dyn ::metrics_catalogue::MetricVisitor) {
+                    #(#visit_metrics)*
+                }
+
+                #[allow(unused_variables)]
+                fn locate_scoped(&self, location: &[usize]) -> Option<::metrics_catalogue::MetricRef<'_>> {
+                    match location {
+                        #(#locate_metrics)*
+                        _ => None,
+                    }
+                }
+            }
+        }
+    }
+
+    /// Implementation of the `Registry` trait, looking up keys relative to the root prefix.
+    /// Roots first look up the location of the full key, falling back to the relative look-up
+    /// for keys selecting their metric by label.
+    pub fn generate_registry(&self, lookup: Option<&KeyLookup>) -> proc_macro2::TokenStream {
+        let struct_name = format_ident!("{}", &self.struct_name);
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let separator = self.key_separator();
+        let with_strip_prefix = |find, extra| {
+            let root_prefix = self.root_prefix();
+            if root_prefix.is_empty() {
                 quote! {
-                    name.strip_prefix(#prefix).and_then(|name| {
-                        #segment
-                    })
+                    ::metrics_catalogue::ScopedRegistry::#find(self, name, #extra #separator)
                 }
             } else {
-                segment
+                quote! {
+                    name.strip_prefix(#root_prefix)
+                        .and_then(|name| name.strip_prefix(#separator))
+                        .and_then(|name| ::metrics_catalogue::ScopedRegistry::#find(self, name, #extra #separator))
+                }
             }
         };
+        let find_counter = with_strip_prefix(quote! { find_scoped_counter }, quote! {});
+        let find_gauge = with_strip_prefix(quote! { find_scoped_gauge }, quote! {});
+        let find_histogram = with_strip_prefix(quote! { find_scoped_histogram }, quote! {});
+        let find_description = with_strip_prefix(quote! { find_scoped_description }, quote! {});
+        let find_unit = with_strip_prefix(quote! { find_scoped_unit }, quote! {});
+        let route_counter = with_strip_prefix(quote! { route_scoped_counter }, quote! { labels, });
+        let route_gauge = with_strip_prefix(quote! { route_scoped_gauge }, quote! { labels, });
+        let route_histogram =
+            with_strip_prefix(quote! { route_scoped_histogram }, quote! { labels, });
 
-        let find_counter = with_strip_prefix(quote! {
-            match name {
-                #(#counters),*
+        // Label-free look-ups are resolved by the key map of a root alone, a miss being unknown to the root.
+        // Labels select an element of an array or family, or a sub-scope routed by them,
+        // only routing to a sub-scope selecting a metric other than the one of the full key.
+        let locate = |as_kind, labelled: bool, find: proc_macro2::TokenStream| match lookup {
+            Some(KeyLookup { locate, routes }) => {
+                let located = quote! {
+                    #locate(name)
+                        .and_then(|location| ::metrics_catalogue::ScopedRegistry::locate_scoped(self, location))
+                        .and_then(|metric| metric.#as_kind())
+                };
+                if !labelled {
+                    located
+                } else if *routes {
+                    quote! {
+                        if labels.is_empty() {
+                            return #located;
+                        }
+                        #find
+                    }
+                } else {
+                    quote! {
+                        if let Some(metric) = #located {
+                            return Some(metric);
+                        }
+                        if labels.is_empty() {
+                            return None;
+                        }
+                        #find
+                    }
+                }
             }
-            #(#sub_counters)*
-        });
-        let find_gauge = with_strip_prefix(quote! {
-            match name {
-                #(#gauges),*
+            None => find,
+        };
+        let find_counter = locate(quote! { as_counter }, false, find_counter);
+        let find_gauge = locate(quote! { as_gauge }, false, find_gauge);
+        let find_histogram = locate(quote! { as_histogram }, false, find_histogram);
+        let route_counter = locate(quote! { as_counter }, true, route_counter);
+        let route_gauge = locate(quote! { as_gauge }, true, route_gauge);
+        let route_histogram = locate(quote! { as_histogram }, true, route_histogram);
+
+        let visit_root = {
+            let root_prefix = self.root_prefix();
+            let root_prefix = if root_prefix.is_empty() {
+                root_prefix
+            } else {
+                format!("{}{}", root_prefix, separator)
+            };
+            quote! {
+                ::metrics_catalogue::ScopedRegistry::visit_scoped(self, #root_prefix, #separator, visitor)
+            }
+        };
+
+        // Misses of a root are counted by its declared `UnknownKeys`, if any
+        let unknown_keys = self.unknown_keys.as_deref().map(|unknown_keys| {
+            let unknown_keys = member(unknown_keys);
+            quote! {
+                fn unknown_keys(&self) -> Option<&::metrics_catalogue::UnknownKeys> {
+                    Some(&self.#unknown_keys)
+                }
             }
-            #(#sub_gauges)*
         });
-        let find_histogram = with_strip_prefix(quote! {
-            match name {
-                #(#histograms),*
+        // Misses are only forwarded to a root recording them, strict roots failing their assertions in debug builds
+        let strict = self.root.as_ref().is_some_and(|root| root.strict);
+        let recorder = if self.dynamic.is_some() || self.unknown_keys.is_some() {
+            Some(quote! { Some(self) })
+        } else if strict {
+            Some(quote! {
+                if cfg!(debug_assertions) {
+                    Some(self)
+                } else {
+                    None
+                }
+            })
+        } else {
+            None
+        };
+        let recorder = recorder.map(|recorder| {
+            quote! {
+                fn recorder(&self) -> Option<&dyn ::metrics_catalogue::Recorder> {
+                    #recorder
+                }
             }
-            #(#sub_histograms)*
         });
 
         quote! {
-            impl ::metrics_catalogue::Registry for #struct_name {
-                fn find_counter(&self, name: &str) -> Option<&::metrics_catalogue::Counter> {
+            impl #impl_generics ::metrics_catalogue::Registry for #struct_name #ty_generics #where_clause {
+                fn find_counter(&self, name: &str) -> Option<&dyn ::metrics_catalogue::CounterMetric> {
                     #find_counter
                 }
 
@@ -157,85 +417,799 @@ impl MetricScope {
                 fn find_histogram(&self, name: &str) -> Option<&dyn ::metrics_catalogue::HistogramMetric> {
                     #find_histogram
                 }
+
+                fn find_description(&self, name: &str) -> Option<&'static str> {
+                    #find_description
+                }
+
+                fn find_unit(&self, name: &str) -> Option<::metrics_catalogue::Unit> {
+                    #find_unit
+                }
+
+                fn route_counter(&self, name: &str, labels: &[::metrics_catalogue::Label]) -> Option<&dyn ::metrics_catalogue::CounterMetric> {
+                    #route_counter
+                }
+
+                fn route_gauge(&self, name: &str, labels: &[::metrics_catalogue::Label]) -> Option<&dyn ::metrics_catalogue::GaugeMetric> {
+                    #route_gauge
+                }
+
+                fn route_histogram(&self, name: &str, labels: &[::metrics_catalogue::Label]) -> Option<&dyn ::metrics_catalogue::HistogramMetric> {
+                    #route_histogram
+                }
+
+                fn visit(&self, visitor: &mut dyn ::metrics_catalogue::MetricVisitor) {
+                    #visit_root
+                }
+
+                #unknown_keys
+
+                #recorder
             }
         }
     }
 
-    #[cfg(feature = "prometheus")]
-    fn generate_prometheus(&self, key_separator: &str, is_root: bool) -> proc_macro2::TokenStream {
-        let struct_name = format_ident!("{}", &self.struct_name);
-        let needs_new_prefix = self.metrics.iter().filter(|m| !m.hidden).count()
-            + self.sub_metrics.iter().filter(|(_, m)| !m.hidden).count()
-            > 0;
-        let new_prefix = if is_root {
-            let s = self.name_override.as_ref().unwrap_or(&self.struct_name);
-            let root_prefix = format!(
-                "{}{}",
-                if s.is_empty() { &self.struct_name } else { s }.to_snake_case(),
-                key_separator
-            );
-            quote! {
-                let prefix = #root_prefix;
+    /// Look-ups of all visible metrics of this scope by their location,
+    /// i.e. the position of their field followed by their index within an array or family
+    fn locate_metrics(&self) -> Vec<proc_macro2::TokenStream> {
+        let metrics = self.metrics.iter().filter(|m| !m.hidden).map(|m| {
+            let instance = member(&m.instance);
+            let position = m.position;
+            let (kind, _) = m.metric_type.kind_tokens();
+            if m.family.is_some() {
+                quote! {
+                    [#position, index] => self.#instance.get_index(*index).map(|metric| ::metrics_catalogue::MetricRef::#kind(metric)),
+                }
+            } else if m.array.is_some() {
+                quote! {
+                    [#position, index] => self.#instance.get(*index).map(|metric| ::metrics_catalogue::MetricRef::#kind(metric)),
+                }
+            } else {
+                quote! {
+                    [#position] => Some(::metrics_catalogue::MetricRef::#kind(&self.#instance)),
+                }
+            }
+        });
+        let sub_metrics = self.sub_metrics.values().filter(|m| !m.hidden).map(|v| {
+            let sub = member(&v.member);
+            let sub = v.scoped(quote! { &self.#sub });
+            let position = v.position;
+            if v.array.is_some() {
+                quote! {
+                    [#position, index, location @ ..] => ::metrics_catalogue::ScopedRegistry::locate_scoped(#sub.get(*index)?, location),
+                }
+            } else {
+                quote! {
+                    [#position, location @ ..] => ::metrics_catalogue::ScopedRegistry::locate_scoped(#sub, location),
+                }
             }
-        } else if needs_new_prefix {
-            let formatter = format!("{{}}{{}}{}", key_separator);
-            let name_formatter = format!("{{}}{}", key_separator);
+        });
+        metrics.chain(sub_metrics).collect()
+    }
 
-            quote! {
-                let prefix = if !prefix.is_empty() {
-                    std::borrow::Cow::Owned(format!(#formatter, prefix, name))
-                } else if !name.is_empty() {
-                    std::borrow::Cow::Owned(format!(#name_formatter, name))
+    /// Visits of all visible metrics of this scope, followed by the ones of its sub-scopes
+    fn visit_metrics(&self) -> Vec<proc_macro2::TokenStream> {
+        let metrics = self.metrics.iter().filter(|m| !m.hidden).map(|m| {
+            let instance = member(&m.instance);
+            let name = &m.name;
+            let (kind, _) = m.metric_type.kind_tokens();
+            let visit = |key: proc_macro2::TokenStream, metric: proc_macro2::TokenStream| {
+                quote! {
+                    visitor.visit(&#key, ::metrics_catalogue::MetricRef::#kind(#metric));
+                }
+            };
+            if m.family.is_some() {
+                let visit = visit(
+                    quote! { format!("{}{}{}{}", prefix, #name, separator, value) },
+                    quote! { metric },
+                );
+                quote! {
+                    for (value, metric) in self.#instance.iter() {
+                        #visit
+                    }
+                }
+            } else if m.array.is_some() {
+                let visit = visit(
+                    quote! { format!("{}{}{}{}", prefix, #name, separator, index) },
+                    quote! { metric },
+                );
+                quote! {
+                    for (index, metric) in self.#instance.iter().enumerate() {
+                        #visit
+                    }
+                }
+            } else {
+                visit(
+                    quote! { format!("{}{}", prefix, #name) },
+                    quote! { &self.#instance },
+                )
+            }
+        });
+        let mut sub_metrics = self
+            .sub_metrics
+            .iter()
+            .filter(|(_, m)| !m.hidden)
+            .collect::<Vec<_>>();
+        sub_metrics.sort_by_key(|(k, _)| *k);
+        let sub_metrics = sub_metrics.into_iter().map(|(k, v)| {
+            let sub = member(&v.member);
+            let sub = v.scoped(quote! { &self.#sub });
+            if v.array.is_some() {
+                quote! {
+                    for (index, sub) in #sub.iter().enumerate() {
+                        let prefix = format!("{}{}{}{}{}", prefix, #k, separator, index, separator);
+                        ::metrics_catalogue::ScopedRegistry::visit_scoped(sub, &prefix, separator, visitor);
+                    }
+                }
+            } else if k.is_empty() {
+                quote! {
+                    ::metrics_catalogue::ScopedRegistry::visit_scoped(#sub, prefix, separator, visitor);
+                }
+            } else {
+                quote! {
+                    ::metrics_catalogue::ScopedRegistry::visit_scoped(
+                        #sub,
+                        &format!("{}{}{}", prefix, #k, separator),
+                        separator,
+                        visitor,
+                    );
+                }
+            }
+        });
+        metrics.chain(sub_metrics).collect()
+    }
+
+    fn match_sub_metrics(
+        &self,
+        find: proc_macro2::TokenStream,
+        extra: proc_macro2::TokenStream,
+    ) -> impl Iterator<Item = proc_macro2::TokenStream> + '_ {
+        self.sub_metrics
+            .iter()
+            .filter(|(_, m)| !m.hidden)
+            .map(move |(k, v)| {
+                let sub = member(&v.member);
+                let sub = v.scoped(quote! { &self.#sub });
+                if let Some(array) = &v.array {
+                    let index_label = &array.index_label;
+                    let labels = if extra.is_empty() {
+                        quote! { &[] }
+                    } else {
+                        quote! { labels }
+                    };
+                    quote! {
+                        .or_else(|| ::metrics_catalogue::scope_index(name, #k, #labels, #index_label, separator)
+                            .and_then(|(i, n)| #sub.get(i).map(|sub| (sub, n)))
+                            .and_then(|(sub, n)| ::metrics_catalogue::ScopedRegistry::#find(sub, n, #extra separator)))
+                    }
+                } else if k.is_empty() {
+                    // Transparent newtype, sharing the keys of its parent
+                    quote! {
+                        .or_else(|| ::metrics_catalogue::ScopedRegistry::#find(#sub, name, #extra separator))
+                    }
                 } else {
-                    std::borrow::Cow::Borrowed("")
+                    quote! {
+                        .or_else(|| name.strip_prefix(#k)
+                            .and_then(|n| n.strip_prefix(separator))
+                            .and_then(|n| ::metrics_catalogue::ScopedRegistry::#find(#sub, n, #extra separator)))
+                    }
+                }
+            })
+    }
+
+    /// Look-ups of sub-scopes selected by the labels of the key, relative to this scope
+    fn match_routed_sub_metrics(
+        &self,
+        find: proc_macro2::TokenStream,
+    ) -> impl Iterator<Item = proc_macro2::TokenStream> + '_ {
+        self.sub_metrics
+            .iter()
+            .filter(|(_, m)| !m.hidden && !m.route.is_empty())
+            .map(move |(_, v)| {
+                let sub = member(&v.member);
+                let sub = v.scoped(quote! { &self.#sub });
+                let conditions = v.route.iter().map(|(key, value)| {
+                    quote! { labels.iter().any(|l| l.key() == #key && l.value() == #value) }
+                });
+                quote! {
+                    if #(#conditions)&&* {
+                        if let Some(metric) = ::metrics_catalogue::ScopedRegistry::#find(#sub, name, labels, separator) {
+                            return Some(metric);
+                        }
+                    }
+                }
+            })
+    }
+
+    pub fn generate_recorder(&self) -> proc_macro2::TokenStream {
+        if self.root.is_none() {
+            return quote! {};
+        }
+        let root_struct = format_ident!("{}", &self.struct_name);
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        // Strict roots assert that the keys of all samples resolve, in debug builds
+        let strict = self.root.as_ref().is_some_and(|root| root.strict);
+        let assert_resolved = |kind: &str| {
+            if !strict {
+                return quote! {};
+            }
+            let kind = format_ident!("{}", kind);
+            quote! {
+                debug_assert!(
+                    false,
+                    "{}",
+                    ::metrics_catalogue::unresolved_key(self, key, ::metrics_catalogue::MetricKind::#kind)
+                );
+            }
+        };
+        // Keys unknown to the catalogue are created in its dynamic registry, if any.
+        // Keys of the catalogue recorded as another kind are dropped, rather than rendered as a second family.
+        let (register, fallback) = match &self.dynamic {
+            Some(dynamic) => {
+                let dynamic = member(dynamic);
+                let register = |kind: &str| {
+                    let register = format_ident!("register_{}", kind);
+                    quote! {
+                        fn #register(&self, key: &::metrics_catalogue::Key, unit: Option<::metrics_catalogue::Unit>, desc: Option<&'static str>) {
+                            if ::metrics_catalogue::resolve_kind(self, key).is_none() {
+                                ::metrics_catalogue::Recorder::#register(&self.#dynamic, key, unit, desc);
+                            }
+                        }
+                    }
+                };
+                let fallback = |record: proc_macro2::TokenStream, kind: &str| {
+                    let assert_resolved = assert_resolved(kind);
+                    quote! {
+                        else if ::metrics_catalogue::resolve_kind(self, key).is_none() {
+                            ::metrics_catalogue::Recorder::#record(&self.#dynamic, key, value);
+                        } else {
+                            #assert_resolved
+                        }
+                    }
+                };
+                (
+                    [
+                        register("counter"),
+                        register("gauge"),
+                        register("histogram"),
+                    ],
+                    [
+                        fallback(quote! { record_histogram }, "Histogram"),
+                        fallback(quote! { increment_counter }, "Counter"),
+                        fallback(quote! { update_gauge }, "Gauge"),
+                    ],
+                )
+            }
+            None => {
+                let record_miss = self.unknown_keys.as_deref().map(|unknown_keys| {
+                    let unknown_keys = member(unknown_keys);
+                    quote! { self.#unknown_keys.record(key.name()); }
+                });
+                let miss = |kind: &str| {
+                    let assert_resolved = assert_resolved(kind);
+                    quote! {
+                        else {
+                            #record_miss
+                            #assert_resolved
+                        }
+                    }
                 };
+                (
+                    [
+                        quote! { fn register_counter(&self, _key: &::metrics_catalogue::Key, _unit: Option<::metrics_catalogue::Unit>, _desc: Option<&'static str>) {} },
+                        quote! { fn register_gauge(&self, _key: &::metrics_catalogue::Key, _unit: Option<::metrics_catalogue::Unit>, _desc: Option<&'static str>) {} },
+                        quote! { fn register_histogram(&self, _key: &::metrics_catalogue::Key, _unit: Option<::metrics_catalogue::Unit>, _desc: Option<&'static str>) {} },
+                    ],
+                    [miss("Histogram"), miss("Counter"), miss("Gauge")],
+                )
             }
+        };
+        let [register_counter, register_gauge, register_histogram] = register;
+        let [histogram_fallback, counter_fallback, gauge_fallback] = fallback;
+        quote! {
+            impl #impl_generics ::metrics_catalogue::Recorder for #root_struct #ty_generics #where_clause {
+                // Keys of the catalogue are already registered
+                #register_counter
+
+                #register_gauge
+
+                #register_histogram
+
+                fn record_histogram(&self, key: &::metrics_catalogue::Key, value: f64) {
+                    if let Some(metric) = ::metrics_catalogue::Registry::route_histogram(self, key.name(), key.labels().as_slice()) {
+                        metric.insert(value);
+                    } #histogram_fallback
+                }
+
+                fn increment_counter(&self, key: &::metrics_catalogue::Key, value: u64) {
+                    if let Some(metric) = ::metrics_catalogue::Registry::route_counter(self, key.name(), key.labels().as_slice()) {
+                        metric.increment(value);
+                    } #counter_fallback
+                }
+
+                fn update_gauge(&self, key: &::metrics_catalogue::Key, value: ::metrics_catalogue::GaugeValue) {
+                    use ::metrics_catalogue::GaugeValue;
+                    if let Some(metric) = ::metrics_catalogue::Registry::route_gauge(self, key.name(), key.labels().as_slice()) {
+                        match value {
+                            GaugeValue::Increment(val) => metric.increase(val),
+                            GaugeValue::Decrement(val) => metric.decrease(val),
+                            GaugeValue::Absolute(val) => metric.set(val),
+                        }
+                    } #gauge_fallback
+                }
+            }
+        }
+    }
+
+    #[cfg(feature = "prometheus")]
+    fn generate_prometheus(&self) -> proc_macro2::TokenStream {
+        let struct_name = format_ident!("{}", &self.struct_name);
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        // Metrics of routed sub-scopes sharing the name of a metric of this scope are rendered along with it,
+        // as a single family, in the order of their keys
+        let mut routed_subs = self
+            .sub_metrics
+            .iter()
+            .filter(|(_, v)| !v.hidden && !v.route.is_empty())
+            .collect::<Vec<_>>();
+        routed_subs.sort_by_key(|(k, _)| *k);
+        let routed_instances = routed_subs
+            .into_iter()
+            .map(|(_, v)| {
+                let sub = member(&v.member);
+                let sub = v.scoped(quote! { &scope.#sub });
+                let labels = with_labels(&[v.labels.clone(), v.route.clone()].concat());
+                quote! { (#sub, #labels) }
+            })
+            .collect::<Vec<_>>();
+        let (routed, routed_values) = if routed_instances.is_empty() {
+            (quote! {}, None)
         } else {
-            quote! {}
+            (
+                quote! {
+                    let routed = instances
+                        .iter()
+                        .flat_map(|(scope, labels)| {
+                            let labels = labels.as_slice();
+                            vec![#(#routed_instances),*]
+                        })
+                        .collect::<Vec<_>>();
+                },
+                Some(quote! { &routed }),
+            )
         };
+        let names = self
+            .metrics
+            .iter()
+            .filter(|m| !m.hidden)
+            .map(|m| m.name.clone())
+            .collect::<Vec<_>>();
+        let mut values_by_name = vec![];
         let fields = self.metrics.iter().filter(|m| !m.hidden).map(|metric| {
-            let instance = format_ident!("{}", metric.instance);
+            let instance = member(&metric.instance);
             let name = metric.name.clone();
-            quote! { ::metrics_catalogue::prometheus::StringRender::render(&self.#instance, &prefix, #name, s); }
-        });
-        let sub_metrics = self
-            .sub_metrics
-            .iter()
-            .filter(|(_, m)| !m.hidden)
-            .map(|(k, _v)| {
-                let sub = format_ident!("{}", k);
-                let name = k.to_string();
-                quote! { ::metrics_catalogue::prometheus::StringRender::render(&self.#sub, &prefix, #name, s); }
+            let rendered_name = metric.rendered_name();
+            let (metric_custom, metric_type) = (metric.custom, metric.metric_type);
+            let description = match &metric.description {
+                Some(description) => quote! { Some(#description) },
+                None => quote! { None },
+            };
+            let unit = metric.unit_tokens();
+            // Metrics declared using their kind are rendered according to that kind
+            let render_as = |metric: proc_macro2::TokenStream| {
+                if metric_custom {
+                    let (_, adapter) = metric_type.kind_tokens();
+                    quote! { &::metrics_catalogue::prometheus::#adapter(#metric) }
+                } else {
+                    metric
+                }
+            };
+            let (first, values) = match &metric.array {
+                Some(array) => {
+                    let index_label = &array.index_label;
+                    let indices = array.indices();
+                    let labels = with_labels(&metric.labels);
+                    let element = render_as(quote! { metric });
+                    (
+                        render_as(quote! { &first.#instance[0] }),
+                        quote! {
+                            for (metric, index) in scope.#instance.iter().zip(&[#(#indices),*]) {
+                                ::metrics_catalogue::prometheus::MetricRender::render_values(
+                                    #element,
+                                    prefix,
+                                    #rendered_name,
+                                    &[&#labels[..], &[(#index_label, *index)]].concat(),
+                                    s,
+                                );
+                            }
+                        },
+                    )
+                }
+                None => {
+                    let labels = if metric.labels.is_empty() {
+                        quote! { labels }
+                    } else {
+                        let labels = with_labels(&metric.labels);
+                        quote! { &#labels }
+                    };
+                    let metric = render_as(quote! { &scope.#instance });
+                    (
+                        render_as(quote! { &first.#instance }),
+                        quote! {
+                            ::metrics_catalogue::prometheus::MetricRender::render_values(
+                                #metric,
+                                prefix,
+                                #rendered_name,
+                                #labels,
+                                s,
+                            );
+                        },
+                    )
+                }
+            };
+            values_by_name.push(quote! {
+                #name => {
+                    for (scope, labels) in instances {
+                        let labels = labels.as_slice();
+                        #values
+                    }
+                }
             });
+            let routed_values = routed_values.as_ref().map(|routed| {
+                quote! {
+                    ::metrics_catalogue::prometheus::ScopedRender::render_values(#routed, prefix, separator, #name, s);
+                }
+            });
+            quote! {
+                if let Some((first, _)) = instances.first().filter(|_| !except.contains(&#name)) {
+                    ::metrics_catalogue::prometheus::MetricRender::render_header(
+                        #first,
+                        prefix,
+                        #rendered_name,
+                        &::metrics_catalogue::prometheus::MetricMetadata {
+                            description: #description,
+                            unit: #unit,
+                            labels: &[],
+                        },
+                        s,
+                    );
+                    for (scope, labels) in instances {
+                        let labels = labels.as_slice();
+                        #values
+                    }
+                    #routed_values
+                    s.push('\n');
+                }
+            }
+        }).collect::<Vec<_>>();
+
+        // Sub-scopes sharing an instance label are rendered as a single family, named after the label.
+        // Routed sub-scopes are rendered as a single family within this scope, labelled by their route.
+        // Arrays of sub-scopes are rendered as a single family, labelled by their index.
+        let mut families = BTreeMap::new();
+        for (k, v) in self.sub_metrics.iter().filter(|(_, m)| !m.hidden) {
+            if let Some(array) = &v.array {
+                let sub = member(&v.member);
+                let sub = v.scoped(quote! { &scope.#sub });
+                let instances = array
+                    .indices()
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, index)| {
+                        let labels = with_labels(
+                            &[v.labels.clone(), vec![(array.index_label.clone(), index)]].concat(),
+                        );
+                        quote! { (&#sub[#i], #labels) }
+                    })
+                    .collect::<Vec<_>>();
+                families
+                    .entry(k.clone())
+                    .or_insert_with(BTreeMap::new)
+                    .insert(k.clone(), instances);
+                continue;
+            }
+            let (family, family_labels) = if !v.route.is_empty() {
+                (String::new(), v.route.clone())
+            } else if let Some(label) = &v.instance_label {
+                (label.clone(), vec![(label.clone(), k.clone())])
+            } else {
+                (k.clone(), vec![])
+            };
+            let sub = member(&v.member);
+            let sub = v.scoped(quote! { &scope.#sub });
+            let labels = with_labels(&[v.labels.clone(), family_labels].concat());
+            families
+                .entry(family)
+                .or_insert_with(BTreeMap::new)
+                .insert(k.clone(), vec![quote! { (#sub, #labels) }]);
+        }
+        let sub_metrics = families.iter().map(|(family, sub_instances)| {
+            if family.is_empty() && routed_values.is_some() {
+                return quote! {
+                    ::metrics_catalogue::prometheus::ScopedRender::render_instances_except(
+                        &routed,
+                        prefix,
+                        separator,
+                        &[#(#names),*],
+                        s,
+                    );
+                };
+            }
+            let sub_instances = sub_instances.values().flatten();
+            let prefix = if family.is_empty() {
+                quote! { prefix }
+            } else {
+                quote! { &format!("{}{}{}", prefix, #family, separator) }
+            };
+            quote! {
+                ::metrics_catalogue::prometheus::ScopedRender::render_instances(
+                    &instances
+                        .iter()
+                        .flat_map(|(scope, labels)| {
+                            let labels = labels.as_slice();
+                            vec![#(#sub_instances),*]
+                        })
+                        .collect::<Vec<_>>(),
+                    #prefix,
+                    separator,
+                    s,
+                );
+            }
+        });
+
+        // The rendering of a root is generated along with its catalogue, once the keys of all its sub-scopes are known
+        let render = if self.root.is_some() {
+            quote! {}
+        } else {
+            let separator = self.key_separator();
+            let formatter = format!("{{}}{{}}{}", separator);
+            let name_formatter = format!("{{}}{}", separator);
+            quote! {
+                impl #impl_generics ::metrics_catalogue::prometheus::StringRender for #struct_name #ty_generics #where_clause {
+                    fn render(&self, prefix: &str, name: &str, s: &mut String) {
+                        let prefix = if !prefix.is_empty() {
+                            std::borrow::Cow::Owned(format!(#formatter, prefix, name))
+                        } else if !name.is_empty() {
+                            std::borrow::Cow::Owned(format!(#name_formatter, name))
+                        } else {
+                            std::borrow::Cow::Borrowed("")
+                        };
+                        ::metrics_catalogue::prometheus::ScopedRender::render_scoped(self, &prefix, #separator, &[], s);
+                    }
+                }
+            }
+        };
 
         quote! {
-            impl ::metrics_catalogue::prometheus::StringRender for #struct_name {
-                fn render(&self, prefix: &str, name: &str, s: &mut String) {
-                    #new_prefix
+            impl #impl_generics ::metrics_catalogue::prometheus::ScopedRender for #struct_name #ty_generics #where_clause {
+                #[allow(unused_variables)]
+                fn render_instances_except(
+                    instances: &[::metrics_catalogue::prometheus::ScopeInstance<Self>],
+                    prefix: &str,
+                    separator: &str,
+                    except: &[&str],
+                    s: &mut String,
+                ) {
+                    #routed
+
                     #(#fields)*
 
                     #(#sub_metrics)*
                 }
+
+                #[allow(unused_variables)]
+                fn render_values(
+                    instances: &[::metrics_catalogue::prometheus::ScopeInstance<Self>],
+                    prefix: &str,
+                    separator: &str,
+                    name: &str,
+                    s: &mut String,
+                ) {
+                    match name {
+                        #(#values_by_name)*
+                        _ => {}
+                    }
+                }
+            }
+
+            #render
+        }
+    }
+
+    /// Rendering of all keys of a root, followed by the keys created at runtime & the samples dropped for unknown keys.
+    /// Like sub-scopes, a root rendered under a prefix or name, e.g. when mounted in a composite registry,
+    /// prepends them to all its keys.
+    #[cfg(feature = "prometheus")]
+    pub fn generate_root_render(&self) -> proc_macro2::TokenStream {
+        let struct_name = format_ident!("{}", &self.struct_name);
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+        let separator = self.key_separator();
+        let root_prefix = self.root_prefix();
+        let root_prefix = if root_prefix.is_empty() {
+            root_prefix
+        } else {
+            format!("{}{}", root_prefix, separator)
+        };
+        // Keys created at runtime are rendered after those of the catalogue, as recorded
+        let dynamic = self.dynamic.as_deref().map(|dynamic| {
+            let dynamic = member(dynamic);
+            quote! {
+                ::metrics_catalogue::prometheus::StringRender::render(&self.#dynamic, &mount, "", s);
+            }
+        });
+        // Misses are rendered by the root itself, or merged with those of the other catalogues when mounted
+        let (render, render_mounted) = match self.unknown_keys.as_deref() {
+            Some(unknown_keys) => {
+                let unknown_keys = member(unknown_keys);
+                (
+                    quote! {
+                        fn render(&self, prefix: &str, name: &str, s: &mut String) {
+                            ::metrics_catalogue::prometheus::StringRender::render_mounted(self, prefix, name, s);
+                            ::metrics_catalogue::prometheus::StringRender::render(&self.#unknown_keys, "", "", s);
+                        }
+                    },
+                    quote! { fn render_mounted },
+                )
+            }
+            None => (quote! {}, quote! { fn render }),
+        };
+        quote! {
+            impl #impl_generics ::metrics_catalogue::prometheus::StringRender for #struct_name #ty_generics #where_clause {
+                #render
+
+                #render_mounted(&self, prefix: &str, name: &str, s: &mut String) {
+                    let mount = if !prefix.is_empty() {
+                        format!("{}{}{}", prefix, name, #separator)
+                    } else if !name.is_empty() {
+                        format!("{}{}", name, #separator)
+                    } else {
+                        String::new()
+                    };
+                    let prefix = format!("{}{}", mount, #root_prefix);
+                    ::metrics_catalogue::prometheus::ScopedRender::render_scoped(self, &prefix, #separator, &[], s);
+                    #dynamic
+                }
             }
         }
     }
 
-    fn mod_name(&self) -> String {
-        let s = self.name_override.as_ref().unwrap_or(&self.struct_name);
-        if s.is_empty() { &self.struct_name } else { s }.to_snake_case()
+    /// Separator used to join the keys of a root, or when looking up keys relative to a sub-scope
+    pub fn key_separator(&self) -> String {
+        self.root
+            .as_ref()
+            .and_then(|root| root.separator.as_deref())
+            .unwrap_or(DEFAULT_SEPARATOR)
+            .to_string()
+    }
+
+    /// Prefix prepended to all keys of a root scope, without the trailing separator
+    pub fn root_prefix(&self) -> String {
+        match self.root.as_ref().map(|root| root.name_override.as_ref()) {
+            Some(Some(name)) => name.to_snake_case(),
+            Some(None) => self.struct_name.to_snake_case(),
+            None => String::new(),
+        }
+    }
+
+    /// Name of the generated catalogue module of a root scope
+    pub fn mod_name(&self) -> String {
+        let s = self.root_prefix();
+        if s.is_empty() {
+            self.struct_name.to_snake_case()
+        } else {
+            s
+        }
+    }
+
+    /// Paths to the scope macros of all visible sub-scopes
+    pub fn sub_scope_paths(&self) -> Vec<(String, Path)> {
+        self.sub_metrics
+            .iter()
+            .filter(|(_, m)| !m.hidden)
+            .map(|(k, v)| (k.clone(), v.macro_path.clone()))
+            .chain(self.metrics.iter().filter(|m| !m.hidden).filter_map(|m| {
+                // Family keys are described by the scope macro sharing their name
+                let mut macro_path = m.family.clone()?;
+                if let Some(last) = macro_path.segments.last_mut() {
+                    last.arguments = syn::PathArguments::None;
+                }
+                Some((m.name.clone(), macro_path))
+            }))
+            .collect()
+    }
+
+    fn has_visible_sub_metrics(&self) -> bool {
+        self.sub_metrics.values().any(|m| !m.hidden)
     }
 }
 
-#[derive(Debug)]
+/// Look-up of the location of each full key of a root, generated along with its catalogue
+pub struct KeyLookup {
+    /// Function returning the location of a key
+    pub locate: proc_macro2::TokenStream,
+    /// Whether any sub-scope of the root is routed by labels
+    pub routes: bool,
+}
+
 pub struct MetricInstance {
     pub key: String,
     pub instance: String,
     pub type_path: TypePath,
     pub name: String,
     pub metric_type: MetricType,
+    pub description: Option<String>,
+    pub unit: Option<String>,
+    pub labels: Vec<(String, String)>,
+    pub array: Option<MetricArray>,
+    /// Key of a metric family
+    pub family: Option<Path>,
+    /// Declared using the `kind` attribute, rather than recognised by its type
+    pub custom: bool,
+    /// Field declaring the metric
+    pub original: syn::Field,
+    /// Position of the field within its scope
+    pub position: usize,
     pub hidden: bool,
 }
 
+/// Fixed-size array of metrics or sub-scopes, distinguished by an index label when rendered
+#[derive(Debug, Clone)]
+pub struct MetricArray {
+    pub len: usize,
+    pub index_label: String,
+}
+
+impl MetricArray {
+    /// Label values of all elements of the array
+    pub fn indices(&self) -> Vec<String> {
+        (0..self.len).map(|i| i.to_string()).collect()
+    }
+}
+
+/// Names of the supported [`metrics::Unit`]s
+pub const UNITS: [&str; 17] = [
+    "count",
+    "percent",
+    "seconds",
+    "milliseconds",
+    "microseconds",
+    "nanoseconds",
+    "tebibytes",
+    "gigibytes",
+    "mebibytes",
+    "kibibytes",
+    "bytes",
+    "terabits_per_second",
+    "gigabits_per_second",
+    "megabits_per_second",
+    "kilobits_per_second",
+    "bits_per_second",
+    "count_per_second",
+];
+
+impl MetricInstance {
+    /// Name of the family rendered for the metric, suffixed by its unit as required by its `# UNIT` line
+    #[cfg(feature = "prometheus")]
+    pub fn rendered_name(&self) -> String {
+        match &self.unit {
+            Some(unit) if !self.name.ends_with(&format!("_{}", unit)) => {
+                format!("{}_{}", self.name, unit)
+            }
+            _ => self.name.clone(),
+        }
+    }
+
+    pub fn unit_tokens(&self) -> proc_macro2::TokenStream {
+        match &self.unit {
+            Some(unit) => {
+                let variant = format_ident!("{}", unit.to_pascal_case());
+                quote! { Some(::metrics_catalogue::Unit::#variant) }
+            }
+            None => quote! { None },
+        }
+    }
+}
+
 #[derive(Debug, Copy, Clone, PartialEq)]
 pub enum MetricType {
     Counter,
@@ -256,11 +1230,35 @@ impl fmt::Display for MetricType {
     }
 }
 
+impl MetricType {
+    /// Names of the kinds which can be declared using the `kind` attribute
+    pub const KINDS: [&'static str; 3] = ["counter", "gauge", "histogram"];
+
+    /// Metric type of an explicitly declared kind
+    pub fn from_kind(kind: &str) -> Option<Self> {
+        match kind {
+            "counter" => Some(MetricType::Counter),
+            "gauge" => Some(MetricType::Gauge),
+            "histogram" => Some(MetricType::Histogram),
+            _ => None,
+        }
+    }
+
+    /// Variant of `MetricKind` checked against the declared kind, and the adapter used to render it
+    pub fn kind_tokens(&self) -> (proc_macro2::TokenStream, proc_macro2::TokenStream) {
+        match self {
+            MetricType::Counter => (quote! { Counter }, quote! { AsCounter }),
+            MetricType::Gauge | MetricType::DiscreteGauge => (quote! { Gauge }, quote! { AsGauge }),
+            MetricType::Histogram => (quote! { Histogram }, quote! { AsHistogram }),
+        }
+    }
+}
+
 impl TryFrom<&Ident> for MetricType {
     type Error = Error;
 
     fn try_from(ident: &Ident) -> Result<Self> {
-        // TODO: improve me
+        // Other metric types are declared using the `kind` attribute
         match ident.to_string().as_str() {
             "Counter" => Ok(MetricType::Counter),
             "Gauge" => Ok(MetricType::Gauge),
@@ -274,28 +1272,93 @@ impl TryFrom<&Ident> for MetricType {
     }
 }
 
-#[derive(Debug)]
 pub struct SubMetric {
+    /// Member of the sub-scope within its parent
+    pub member: String,
+    /// Position of the field of the sub-scope within its parent
+    pub position: usize,
     pub ident: String,
+    /// Type of the field, i.e. the sub-scope or an array of sub-scopes
+    pub ty: syn::Type,
+    pub macro_path: Path,
+    pub labels: Vec<(String, String)>,
+    pub instance_label: Option<String>,
+    pub route: Vec<(String, String)>,
+    pub array: Option<MetricArray>,
     pub hidden: bool,
 }
 
+impl SubMetric {
+    /// Expression referencing the sub-scope, passed through its scope macro.
+    /// A field of another type, e.g. a metric whose kind isn't declared, is then only reported once,
+    /// as its unresolved scope macro, rather than by each use of the sub-scope.
+    fn scoped(&self, expr: proc_macro2::TokenStream) -> proc_macro2::TokenStream {
+        let macro_path = &self.macro_path;
+        quote! { #macro_path!(@scope #expr) }
+    }
+}
+
 impl AsRef<str> for SubMetric {
     fn as_ref(&self) -> &str {
         &self.ident
     }
 }
 
+/// Static labels of a metric or sub-scope, appended to the ones inherited from its parent scopes
+#[cfg(feature = "prometheus")]
+fn with_labels(labels: &[(String, String)]) -> proc_macro2::TokenStream {
+    if labels.is_empty() {
+        quote! { labels.to_vec() }
+    } else {
+        let labels = labels.iter().map(|(k, v)| quote! { (#k, #v) });
+        quote! { [labels, &[#(#labels),*][..]].concat() }
+    }
+}
+
+/// Member of a field, by its name or its position within a tuple struct
+fn member(name: &str) -> Member {
+    match name.parse::<usize>() {
+        Ok(index) => Member::Unnamed(Index::from(index)),
+        Err(_) => Member::Named(format_ident!("{}", name)),
+    }
+}
+
+/// Initialise a metric declared using its kind, which may not have a const constructor
+fn const_default_init(k: &str, v: &str) -> proc_macro2::TokenStream {
+    let k = member(k);
+    let v = syn::parse_str::<Path>(v).unwrap_or_else(|_| panic!("invalid path: {}", v));
+    quote! { #k: <#v as ::metrics_catalogue::ConstDefault>::DEFAULT }
+}
+
 fn default_init((k, v): (impl AsRef<str>, impl AsRef<str>)) -> proc_macro2::TokenStream {
-    let k = format_ident!("{}", k.as_ref());
+    let k = member(k.as_ref());
     let v = syn::parse_str::<Path>(v.as_ref())
         .unwrap_or_else(|_| panic!("invalid path: {}", v.as_ref()));
+    // Markers of unused generic parameters have no const constructor
+    if v.segments
+        .last()
+        .is_some_and(|last| last.ident == "PhantomData")
+    {
+        return quote! { #k: ::core::marker::PhantomData };
+    }
     quote! { #k: #v::new() }
 }
 
+/// Initialise a metric or sub-scope, or each element of an array of them
+fn array_init(k: &str, v: &str, array: Option<&MetricArray>) -> proc_macro2::TokenStream {
+    match array {
+        Some(MetricArray { len, .. }) => {
+            let k = member(k);
+            let v = syn::parse_str::<Path>(v).unwrap_or_else(|_| panic!("invalid path: {}", v));
+            quote! { #k: [<#v as ::metrics_catalogue::ConstDefault>::DEFAULT; #len] }
+        }
+        None => default_init((k, v)),
+    }
+}
+
 fn match_instance(metric: &MetricInstance, as_trait: Option<&str>) -> proc_macro2::TokenStream {
     let name = format_ident!("{}", metric.name);
-    let instance = format_ident!("{}", metric.instance);
+    let instance = member(&metric.instance);
     let quoted_name = name.to_string();
     if let Some(as_trait) = as_trait {
         let as_trait = format_ident!("{}", as_trait);
@@ -315,7 +1378,68 @@ fn match_metric_names<'a>(
     instances
         .iter()
         .filter(|m| !m.hidden)
-        .filter(move |m| metric_types.iter().any(|t| m.metric_type == *t))
+        .filter(move |m| m.array.is_none() && m.family.is_none())
+        .filter(move |m| metric_types.contains(&m.metric_type))
         .map(move |m| match_instance(m, as_trait))
         .chain(std::iter::once(fallthrough))
 }
+
+/// Look-ups of the members of arrays or families of metrics, by their relative key or label
+fn match_indexed_metrics<'a>(
+    instances: &'a [MetricInstance],
+    metric_types: &'a [MetricType],
+    as_trait: Option<&'a str>,
+) -> impl Iterator<Item = proc_macro2::TokenStream> + 'a {
+    instances
+        .iter()
+        .filter(|m| !m.hidden)
+        .filter(move |m| metric_types.contains(&m.metric_type))
+        .filter_map(move |m| {
+            let instance = member(&m.instance);
+            let name = &m.name;
+            let found = if let Some(array) = &m.array {
+                let index_label = &array.index_label;
+                quote! {
+                    ::metrics_catalogue::metric_index(name, #name, labels, #index_label, separator)
+                        .and_then(|i| self.#instance.get(i))
+                }
+            } else if m.family.is_some() {
+                quote! {
+                    name.strip_prefix(#name)
+                        .and_then(|key| self.#instance.find_key(key, labels, separator))
+                }
+            } else {
+                return None;
+            };
+            Some(if let Some(as_trait) = as_trait {
+                let as_trait = format_ident!("{}", as_trait);
+                quote! { .or_else(|| #found.map(|m| m as &dyn ::metrics_catalogue::#as_trait)) }
+            } else {
+                quote! { .or_else(|| #found) }
+            })
+        })
+}
+
+/// Look-up of the metadata shared by all members of an array or family of metrics
+fn match_member(
+    metric: &MetricInstance,
+    value: proc_macro2::TokenStream,
+) -> Option<proc_macro2::TokenStream> {
+    let name = &metric.name;
+    let instance = member(&metric.instance);
+    let is_member = if let Some(array) = &metric.array {
+        let index_label = &array.index_label;
+        quote! { ::metrics_catalogue::metric_index(name, #name, &[], #index_label, separator).is_some() }
+    } else if metric.family.is_some() {
+        quote! {
+            name.strip_prefix(#name)
+                .and_then(|key| self.#instance.find_key(key, &[], separator))
+                .is_some()
+        }
+    } else {
+        return None;
+    };
+    Some(quote! {
+        .or_else(|| (name == #name || #is_member).then(|| #value).flatten())
+    })
+}
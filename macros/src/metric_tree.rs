@@ -1,253 +1,998 @@
-use crate::ast::{Attributes, Struct, TypePath};
-use crate::metric_scope::{MetricInstance, MetricScope, MetricType, SubMetric};
-use crate::scoped_catalogue::ScopedCatalogue;
-use crate::DEFAULT_SEPARATOR;
-use inflector::Inflector;
-use quote::{format_ident, quote};
-use std::collections::{HashMap, HashSet};
+use crate::ast::{AttributeValue, Attributes, Struct, TypePath};
+use crate::family_key::{family_label, family_variants, variant_key};
+use crate::metric_scope::{
+    KeyLookup, MetricArray, MetricInstance, MetricScope, MetricType, SubMetric, UNITS,
+};
+use crate::naming::{accessor_ident, check_label, check_name};
+use crate::scope_composition::ScopeDescription;
+use crate::scoped_catalogue::{FamilyCatalogue, KeyPrefix, ScopedCatalogue};
+use crate::DEFAULT_INDEX_LABEL;
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use std::collections::HashMap;
 use std::convert::TryFrom;
-use std::iter::once;
-use syn::{Data, DeriveInput, Error, Result, Type};
+use syn::{
+    Data, DeriveInput, Error, Expr, ExprLit, Fields, GenericArgument, Lit, Path, PathArguments,
+    PathSegment, Result, Type,
+};
 
-#[derive(Default, Debug)]
+/// Hierarchical mapping of a scope & its (nested) sub-scopes
 pub struct MetricTree {
-    scopes: HashMap<String, MetricScope>,
-    required_scopes: HashSet<String>,
-    root_scope: Option<String>,
-    key_separator: String,
+    scope: MetricScope,
+    sub_scopes: HashMap<String, MetricTree>,
+    /// Label & the key constants & label values of the keys of each metric family, by field
+    families: HashMap<String, (String, Vec<(Ident, String)>)>,
 }
 
 impl MetricTree {
-    pub fn is_complete(&self) -> bool {
-        self.root_scope.is_some()
-            && self
-                .required_scopes
-                .iter()
-                .all(|name| self.scopes.contains_key(name))
+    pub fn from_description(description: &ScopeDescription) -> Result<Self> {
+        let mut sub_scopes = HashMap::new();
+        let mut families = HashMap::new();
+        for (field, sub_scope) in &description.sub_scopes {
+            if let Data::Enum(data) = &sub_scope.input.data {
+                families.insert(
+                    field.value(),
+                    (
+                        family_label(&sub_scope.input)?,
+                        family_variants(&sub_scope.input, data)?,
+                    ),
+                );
+            } else {
+                sub_scopes.insert(field.value(), MetricTree::from_description(sub_scope)?);
+            }
+        }
+        Ok(MetricTree {
+            scope: parse_scope(&description.input)?,
+            sub_scopes,
+            families,
+        })
     }
 
-    pub fn generate(&self) -> proc_macro2::TokenStream {
-        let root = self.generate_root();
-        let catalogue = self.generate_catalogue();
-        let root_name = self.root_scope.as_ref().expect("No root scope");
-        let scopes = self
-            .scopes
-            .values()
-            .map(|scope| scope.generate(&self.key_separator, scope.struct_name == *root_name));
-        let combined = once(root).chain(once(catalogue)).chain(scopes);
-
-        quote! {
-            #(#combined)*
+    fn generate_scoped_catalogue(
+        &self,
+        mod_name: &str,
+        len: Option<usize>,
+        scope: &AccessorScope,
+    ) -> ScopedCatalogue {
+        ScopedCatalogue {
+            mod_name: mod_name.to_string(),
+            len,
+            metrics: self
+                .scope
+                .metrics
+                .iter()
+                .filter(|m| !m.hidden)
+                .map(|m| {
+                    let len = m.array.as_ref().map(|array| array.len);
+                    (m.key.clone(), m.name.clone(), len)
+                })
+                .collect(),
+            families: self
+                .scope
+                .metrics
+                .iter()
+                .filter(|m| !m.hidden)
+                .filter_map(|m| {
+                    let (_, variants) = self.families.get(&m.name)?;
+                    let keys = variants
+                        .iter()
+                        .map(|(variant, value)| (variant_key(variant), value.clone()))
+                        .collect::<Vec<_>>();
+                    // Accessors of family members are declared within the module of the family
+                    let family_scope = scope.sub_module();
+                    let accessors = keys
+                        .iter()
+                        .enumerate()
+                        .map(|(index, (key, _))| {
+                            family_scope
+                                .clone()
+                                .within(m.position)
+                                .member(index)
+                                .accessor(&key.to_string(), false)
+                        })
+                        .collect();
+                    Some(FamilyCatalogue {
+                        name: m.name.clone(),
+                        keys,
+                        accessors,
+                    })
+                })
+                .collect(),
+            sub_scopes: self
+                .sub_scopes
+                .iter()
+                .map(|(k, v)| {
+                    let sub = &self.scope.sub_metrics[k];
+                    let sub_scope = if k.is_empty() {
+                        scope.clone().within(sub.position)
+                    } else {
+                        scope.sub_module().within(sub.position)
+                    };
+                    let sub_scope = match &sub.array {
+                        Some(_) => sub_scope.indexed(format_ident!("{}_index", k)),
+                        None => sub_scope,
+                    };
+                    let len = sub.array.as_ref().map(|array| array.len);
+                    (k.clone(), v.generate_scoped_catalogue(k, len, &sub_scope))
+                })
+                .collect(),
+            accessors: self
+                .scope
+                .metrics
+                .iter()
+                .filter(|m| !m.hidden && m.family.is_none())
+                .map(|m| {
+                    scope
+                        .clone()
+                        .within(m.position)
+                        .accessor(&m.name, m.array.is_some())
+                })
+                .collect(),
+            descriptors: None,
+            root: None,
         }
     }
 
-    fn generate_root(&self) -> proc_macro2::TokenStream {
-        let root_name = self.root_scope.clone().expect("No root scope");
-        let root_struct = format_ident!("{}", root_name);
-        let recorder = quote! {
-            impl ::metrics_catalogue::Recorder for #root_struct {
-                // The following are unused in Stats
-                fn register_counter(&self, _key: &::metrics_catalogue::Key, _unit: Option<::metrics_catalogue::Unit>, _desc: Option<&'static str>) {}
+    pub fn generate_catalogue(&self) -> Result<proc_macro2::TokenStream> {
+        let key_separator = self.scope.key_separator();
+        let root_prefix = self.scope.root_prefix();
+        let prefix = if root_prefix.is_empty() {
+            String::new()
+        } else {
+            format!("{}{}", root_prefix, key_separator)
+        };
 
-                fn register_gauge(&self, _key: &::metrics_catalogue::Key, _unit: Option<::metrics_catalogue::Unit>, _desc: Option<&'static str>) {}
+        self.check_keys(
+            &prefix,
+            "",
+            &key_separator,
+            &mut HashMap::new(),
+            &mut HashMap::new(),
+        )?;
+        let mut descriptors = vec![];
+        self.generate_descriptors(&prefix, "", &key_separator, &[], &mut descriptors);
+        let mut locations = vec![];
+        self.generate_locations(&prefix, &[], &key_separator, &mut locations);
+        let mut catalogue =
+            self.generate_scoped_catalogue(&self.scope.mod_name(), None, &AccessorScope::root());
+        catalogue.descriptors = Some(descriptors);
+        catalogue.root = Some(generate_root_trait(&locations));
+        let keys = catalogue.generate_prefix_keys(&KeyPrefix::Single(prefix), &key_separator);
 
-                fn register_histogram(&self, _key: &::metrics_catalogue::Key, _unit: Option<::metrics_catalogue::Unit>, _desc: Option<&'static str>) {}
+        let mod_name = format_ident!("{}", self.scope.mod_name());
+        let struct_name = format_ident!("{}", self.scope.struct_name);
+        let (impl_generics, ty_generics, where_clause) = self.scope.generics.split_for_impl();
+        let registry = self.scope.generate_registry(Some(&KeyLookup {
+            locate: quote! { <Self as #mod_name::Root>::locate },
+            routes: self.has_routes(),
+        }));
+        let recorder = self.scope.generate_recorder();
+        #[cfg(feature = "prometheus")]
+        let render = self.scope.generate_root_render();
+        #[cfg(not(feature = "prometheus"))]
+        let render = quote! {};
+        Ok(quote! {
+            #keys
 
-                fn record_histogram(&self, key: &::metrics_catalogue::Key, value: f64) {
-                    if let Some(metric) = ::metrics_catalogue::Registry::find_histogram(self, key.name()) {
-                        metric.insert(value);
-                    }
+            impl #impl_generics #mod_name::Root for #struct_name #ty_generics #where_clause {}
+
+            #registry
+
+            #recorder
+
+            #render
+        })
+    }
+
+    /// Location of the metric of each full key within the tree,
+    /// as the positions of the fields leading to it, along with their index within an array or family
+    fn generate_locations(
+        &self,
+        prefix: &str,
+        location: &[usize],
+        separator: &str,
+        locations: &mut Vec<(String, Vec<usize>)>,
+    ) {
+        for m in self.scope.metrics.iter().filter(|m| !m.hidden) {
+            let key = format!("{}{}", prefix, m.name);
+            let metric_location = [location, &[m.position]].concat();
+            if let Some(array) = &m.array {
+                for index in 0..array.len {
+                    locations.push((
+                        format!("{}{}{}", key, separator, index),
+                        [&metric_location[..], &[index]].concat(),
+                    ));
+                }
+            } else if let Some((_, variants)) = self.families.get(&m.name) {
+                for (index, (_, value)) in variants.iter().enumerate() {
+                    locations.push((
+                        format!("{}{}{}", key, separator, value),
+                        [&metric_location[..], &[index]].concat(),
+                    ));
+                }
+            } else {
+                locations.push((key, metric_location));
+            }
+        }
+        for (k, tree) in &self.sub_scopes {
+            let sub = &self.scope.sub_metrics[k];
+            let sub_location = [location, &[sub.position]].concat();
+            if let Some(array) = &sub.array {
+                for index in 0..array.len {
+                    tree.generate_locations(
+                        &format!("{}{}{}{}{}", prefix, k, separator, index, separator),
+                        &[&sub_location[..], &[index]].concat(),
+                        separator,
+                        locations,
+                    );
                 }
+            } else if k.is_empty() {
+                // Transparent newtypes share the keys of their parent
+                tree.generate_locations(prefix, &sub_location, separator, locations);
+            } else {
+                tree.generate_locations(
+                    &format!("{}{}{}", prefix, k, separator),
+                    &sub_location,
+                    separator,
+                    locations,
+                );
+            }
+        }
+    }
 
-                fn increment_counter(&self, key: &::metrics_catalogue::Key, value: u64) {
-                    if let Some(metric) = ::metrics_catalogue::Registry::find_counter(self, key.name()) {
-                        metric.increment(value);
+    /// Whether any sub-scope within the tree is routed by labels
+    fn has_routes(&self) -> bool {
+        self.sub_scopes
+            .iter()
+            .any(|(k, tree)| !self.scope.sub_metrics[k].route.is_empty() || tree.has_routes())
+    }
+
+    /// Descriptors of all keys within the tree, along with the labels inherited from their parent scopes
+    fn generate_descriptors(
+        &self,
+        prefix: &str,
+        path: &str,
+        separator: &str,
+        labels: &[TokenStream],
+        descriptors: &mut Vec<TokenStream>,
+    ) {
+        for m in self.scope.metrics.iter().filter(|m| !m.hidden) {
+            let (kind, _) = m.metric_type.kind_tokens();
+            let unit = m.unit_tokens();
+            let description = match &m.description {
+                Some(description) => quote! { Some(#description) },
+                None => quote! { None },
+            };
+            let metric_labels = labels
+                .iter()
+                .cloned()
+                .chain(m.labels.iter().map(|(k, v)| quote! { (#k, #v) }))
+                .collect::<Vec<_>>();
+            let mut descriptor = |key: String, path: String, labels: Vec<TokenStream>| {
+                descriptors.push(quote! {
+                    ::metrics_catalogue::MetricDescriptor {
+                        key: #key,
+                        kind: ::metrics_catalogue::MetricKind::#kind,
+                        unit: #unit,
+                        description: #description,
+                        labels: &[#(#labels),*],
+                        path: #path,
                     }
+                });
+            };
+            let key = format!("{}{}", prefix, m.name);
+            let path = format!("{}{}", path, m.instance);
+            if let Some(array) = &m.array {
+                for index in array.indices() {
+                    let index_label = &array.index_label;
+                    let labels = [
+                        metric_labels.clone(),
+                        vec![quote! { (#index_label, #index) }],
+                    ];
+                    descriptor(
+                        format!("{}{}{}", key, separator, index),
+                        format!("{}[{}]", path, index),
+                        labels.concat(),
+                    );
+                }
+            } else if let (Some(family), Some((label, variants))) =
+                (&m.family, self.families.get(&m.name))
+            {
+                let family_name = family
+                    .segments
+                    .last()
+                    .map(|segment| segment.ident.to_string())
+                    .unwrap_or_default();
+                for (variant, value) in variants {
+                    let label = quote! { (#label, #value) };
+                    descriptor(
+                        format!("{}{}{}", key, separator, value),
+                        format!("{}[{}::{}]", path, family_name, variant),
+                        [metric_labels.clone(), vec![label]].concat(),
+                    );
+                }
+            } else {
+                descriptor(key, path, metric_labels);
+            }
+        }
+        // Sorted, for the descriptors to be generated deterministically
+        let mut sub_scopes = self.sub_scopes.iter().collect::<Vec<_>>();
+        sub_scopes.sort_by_key(|(k, _)| *k);
+        for (k, tree) in sub_scopes {
+            let sub = &self.scope.sub_metrics[k];
+            let family_labels = if !sub.route.is_empty() {
+                sub.route.clone()
+            } else if let Some(label) = &sub.instance_label {
+                vec![(label.clone(), k.clone())]
+            } else {
+                vec![]
+            };
+            let sub_labels = labels
+                .iter()
+                .cloned()
+                .chain(
+                    sub.labels
+                        .iter()
+                        .chain(&family_labels)
+                        .map(|(k, v)| quote! { (#k, #v) }),
+                )
+                .collect::<Vec<_>>();
+            if let Some(array) = &sub.array {
+                let index_label = &array.index_label;
+                for index in array.indices() {
+                    let labels = [sub_labels.clone(), vec![quote! { (#index_label, #index) }]];
+                    tree.generate_descriptors(
+                        &format!("{}{}{}{}{}", prefix, k, separator, index, separator),
+                        &format!("{}{}[{}].", path, sub.member, index),
+                        separator,
+                        &labels.concat(),
+                        descriptors,
+                    );
                 }
+            } else if k.is_empty() {
+                // Transparent newtypes share the keys of their parent
+                tree.generate_descriptors(
+                    prefix,
+                    &format!("{}{}.", path, sub.member),
+                    separator,
+                    &sub_labels,
+                    descriptors,
+                );
+            } else {
+                tree.generate_descriptors(
+                    &format!("{}{}{}", prefix, k, separator),
+                    &format!("{}{}.", path, sub.member),
+                    separator,
+                    &sub_labels,
+                    descriptors,
+                );
+            }
+        }
+    }
 
-                fn update_gauge(&self, key: &::metrics_catalogue::Key, value: ::metrics_catalogue::GaugeValue) {
-                    use ::metrics_catalogue::GaugeValue;
-                    if let Some(metric) = ::metrics_catalogue::Registry::find_gauge(self, key.name()) {
-                        match value {
-                            GaugeValue::Increment(val) => metric.increase(val),
-                            GaugeValue::Decrement(val) => metric.decrease(val),
-                            GaugeValue::Absolute(val) => metric.set(val),
-                        }
+    /// Check that all keys & key constants within the tree are unique,
+    /// as keys of sub-scopes may collide with other keys once joined by the separator
+    fn check_keys<'a>(
+        &'a self,
+        prefix: &str,
+        mod_path: &str,
+        separator: &str,
+        keys: &mut HashMap<String, &'a syn::Field>,
+        constants: &mut HashMap<String, &'a syn::Field>,
+    ) -> Result<()> {
+        for m in self.scope.metrics.iter().filter(|m| !m.hidden) {
+            let mut metric_keys = vec![m.name.clone()];
+            if let Some(array) = &m.array {
+                metric_keys.extend(
+                    array
+                        .indices()
+                        .into_iter()
+                        .map(|index| format!("{}{}{}", m.name, separator, index)),
+                );
+            }
+            if let Some((_, variants)) = self.families.get(&m.name) {
+                metric_keys.extend(
+                    variants
+                        .iter()
+                        .map(|(_, value)| format!("{}{}{}", m.name, separator, value)),
+                );
+            }
+            for key in metric_keys {
+                let key = format!("{}{}", prefix, key);
+                if let Some(first) = keys.insert(key.clone(), &m.original) {
+                    return Err(duplicate_error(
+                        &m.original,
+                        first,
+                        &format!("Duplicate metric key `{}`", key),
+                    ));
+                }
+            }
+            if mod_path.is_empty() && m.key == "METRICS" {
+                return Err(Error::new_spanned(
+                    &m.original,
+                    "The key constant `METRICS` of a root is reserved for the descriptors of its catalogue",
+                ));
+            }
+            // Elements of an array of sub-scopes share their key constants
+            let constant = format!("{}::{}", mod_path, m.key);
+            if let Some(first) = constants
+                .insert(constant, &m.original)
+                .filter(|first| !std::ptr::eq(*first, &m.original))
+            {
+                return Err(duplicate_error(
+                    &m.original,
+                    first,
+                    &format!("Duplicate key constant `{}`", m.key),
+                ));
+            }
+        }
+        // Sorted, for the reported collisions to be deterministic
+        let mut sub_scopes = self.sub_scopes.iter().collect::<Vec<_>>();
+        sub_scopes.sort_by_key(|(k, _)| *k);
+        for (k, tree) in sub_scopes {
+            let sub = &self.scope.sub_metrics[k];
+            // Metrics of a routed sub-scope are rendered within the family of the metric sharing their name
+            if !sub.hidden && !sub.route.is_empty() {
+                for routed in tree.scope.metrics.iter().filter(|m| !m.hidden) {
+                    let kind = routed.metric_type.kind_tokens().0.to_string();
+                    if let Some(m) = self.scope.metrics.iter().find(|m| {
+                        !m.hidden
+                            && m.name == routed.name
+                            && m.metric_type.kind_tokens().0.to_string() != kind
+                    }) {
+                        return Err(Error::new_spanned(
+                            &m.original,
+                            format!(
+                                "The routed metric `{}` of the sub-scope `{}` should be of the same kind as this metric, found {}",
+                                routed.name,
+                                k,
+                                kind.to_lowercase()
+                            ),
+                        ));
                     }
                 }
             }
-        };
+            let (prefixes, mod_path) = if k.is_empty() {
+                (vec![prefix.to_string()], mod_path.to_string())
+            } else {
+                let prefixes = match &sub.array {
+                    Some(array) => array
+                        .indices()
+                        .into_iter()
+                        .map(|index| format!("{}{}{}{}{}", prefix, k, separator, index, separator))
+                        .collect(),
+                    None => vec![format!("{}{}{}", prefix, k, separator)],
+                };
+                (prefixes, format!("{}::{}", mod_path, k))
+            };
+            for prefix in prefixes {
+                tree.check_keys(&prefix, &mod_path, separator, keys, constants)?;
+            }
+        }
+        Ok(())
+    }
+}
 
+/// Trait implemented by the root of a catalogue, looking up the location of each full key
+/// through a perfect hash map generated at compile time
+fn generate_root_trait(locations: &[(String, Vec<usize>)]) -> TokenStream {
+    let locate = if locations.is_empty() {
         quote! {
-            #recorder
+            fn locate(_key: &str) -> Option<&'static [usize]> {
+                None
+            }
+        }
+    } else {
+        let keys = locations
+            .iter()
+            .map(|(key, _)| key.as_str())
+            .collect::<Vec<_>>();
+        let state = phf_generator::generate_hash(&keys);
+        let hash_key = state.key;
+        let disps = state.disps.iter().map(|(d1, d2)| quote! { (#d1, #d2) });
+        let entries = state.map.iter().map(|&index| {
+            let (key, location) = &locations[index];
+            quote! { (#key, &[#(#location),*]) }
+        });
+        quote! {
+            fn locate(key: &str) -> Option<&'static [usize]> {
+                static LOCATIONS: ::metrics_catalogue::phf::Map<&'static str, &'static [usize]> = ::metrics_catalogue::phf::Map {
+                    key: #hash_key,
+                    disps: &[#(#disps),*],
+                    entries: &[#(#entries),*],
+                };
+                LOCATIONS.get(key).copied()
+            }
+        }
+    };
+    quote! {
+        /// Root of the catalogue, accessed through the accessors of its keys
+        pub trait Root: ::metrics_catalogue::ScopedRegistry {
+            /// Location of the metric of a full key within the root
+            #[doc(hidden)]
+            #locate
         }
     }
+}
 
-    fn generate_scoped_catalogue(&self, mod_name: &str, scope_name: &str) -> ScopedCatalogue {
-        let scope = self.scopes.get(scope_name).expect("Invalid scope");
-        ScopedCatalogue {
-            mod_name: mod_name.to_string(),
-            metrics: scope
-                .metrics
-                .iter()
-                .filter(|m| !m.hidden)
-                .map(|m| (m.key.clone(), m.name.clone()))
-                .collect(),
-            sub_scopes: scope
-                .sub_metrics
-                .iter()
-                .filter(|(_, m)| !m.hidden)
-                .map(|(k, v)| (k.clone(), self.generate_scoped_catalogue(k, &v.ident)))
-                .collect(),
+/// Scope of the accessors generated within a module of a catalogue
+#[derive(Clone)]
+struct AccessorScope {
+    /// Path to the `Root` trait of the catalogue, relative to the module
+    root: TokenStream,
+    /// Projections leading from the root to the scope
+    projections: Vec<Projection>,
+    /// Indices of the enclosing arrays of sub-scopes
+    indices: Vec<Ident>,
+}
+
+/// Projection of a field, or of an element of an array or family
+#[derive(Clone)]
+enum Projection {
+    Field(usize),
+    Index(TokenStream),
+}
+
+impl AccessorScope {
+    fn root() -> Self {
+        AccessorScope {
+            root: quote! { Root },
+            projections: vec![],
+            indices: vec![],
         }
     }
 
-    fn generate_catalogue(&self) -> proc_macro2::TokenStream {
-        let root_struct = self.root_scope.clone().expect("No root struct");
-        let root_scope = self.scopes.get(&root_struct).expect("No root scope");
-        let root_mod = root_scope
-            .name_override
-            .as_ref()
-            .unwrap_or(&root_struct)
-            .to_snake_case();
-        let prefix = if root_mod.is_empty() {
-            String::new()
-        } else {
-            format!("{}{}", root_mod, self.key_separator)
-        };
-        let root_mod = if root_mod.is_empty() {
-            root_struct.to_snake_case()
+    /// Scope of a nested module
+    fn sub_module(&self) -> Self {
+        let root = &self.root;
+        AccessorScope {
+            root: quote! { super::#root },
+            ..self.clone()
+        }
+    }
+
+    /// Scope of the field at the provided position
+    fn within(mut self, position: usize) -> Self {
+        self.projections.push(Projection::Field(position));
+        self
+    }
+
+    /// Scope of an element of an array of sub-scopes
+    fn indexed(mut self, index: Ident) -> Self {
+        self.projections.push(Projection::Index(quote! { #index }));
+        self.indices.push(index);
+        self
+    }
+
+    /// Scope of the member of a family at the provided position
+    fn member(mut self, index: usize) -> Self {
+        self.projections.push(Projection::Index(quote! { #index }));
+        self
+    }
+
+    /// Accessor of the metric projected from the root, selecting an element of an array of metrics by index
+    fn accessor(&self, name: &str, array: bool) -> TokenStream {
+        let ident = accessor_ident(name);
+        let root = &self.root;
+        let (index, element) = if array {
+            (
+                Some(quote! { index: usize }),
+                Some(Projection::Index(quote! { index })),
+            )
         } else {
-            root_mod
+            (None, None)
         };
-
-        self.generate_scoped_catalogue(&root_mod, &root_struct)
-            .generate_prefix_keys(&prefix, &self.key_separator)
+        let indices = self.indices.iter().map(|index| quote! { #index: usize });
+        let projections = self.projections.iter().cloned().chain(element);
+        let (mut ty, mut expr, mut bounds) = (quote! { R }, quote! { root }, vec![]);
+        for projection in projections {
+            match projection {
+                Projection::Field(position) => {
+                    bounds.push(quote! { #ty: ::metrics_catalogue::ScopeField<#position> });
+                    expr = quote! { ::metrics_catalogue::ScopeField::<#position>::field(#expr) };
+                    ty = quote! { <#ty as ::metrics_catalogue::ScopeField<#position>>::Field };
+                }
+                Projection::Index(index) => {
+                    bounds.push(quote! { #ty: ::core::ops::Index<usize> });
+                    expr = quote! { &#expr[#index] };
+                    ty = quote! { <#ty as ::core::ops::Index<usize>>::Output };
+                }
+            }
+        }
+        quote! {
+            #[inline]
+            pub fn #ident<R: #root>(root: &R, #(#indices,)* #index) -> &#ty
+            where
+                #(#bounds),*
+            {
+                #expr
+            }
+        }
     }
+}
 
-    pub fn parse_struct(&mut self, input: DeriveInput) -> Result<()> {
-        let struct_data = match &input.data {
-            Data::Struct(data) => Struct::from_syn(&input, data),
-            Data::Enum(_) | Data::Union(_) => Err(Error::new_spanned(
-                &input,
-                "Metrics are only supported as structs",
-            )),
-        }?;
-
-        if matches!(struct_data.attributes, Attributes::Root(_)) && self.root_scope.is_some() {
-            return Err(Error::new_spanned(
-                &input,
-                format!(
-                    "Duplicate root attribute previously detected on {}",
-                    self.root_scope.as_ref().expect("No root scope")
+/// Parse a struct into the scope of its own metrics & sub-scopes
+pub fn parse_scope(input: &DeriveInput) -> Result<MetricScope> {
+    let struct_data = match &input.data {
+        Data::Struct(data) => Struct::from_syn(input, data),
+        Data::Enum(data) => Err(Error::new_spanned(
+            data.enum_token,
+            "Metrics are only supported as structs",
+        )),
+        Data::Union(data) => Err(Error::new_spanned(
+            data.union_token,
+            "Metrics are only supported as structs",
+        )),
+    }?;
+
+    let root = match &struct_data.attributes {
+        Attributes::Root(root) => Some(root.clone()),
+        Attributes::Struct(_) => None,
+    };
+    let mut metrics = vec![];
+    let mut other_fields = HashMap::new();
+    let mut dynamic: Option<(&syn::Field, String)> = None;
+    let mut unknown_keys: Option<(&syn::Field, String)> = None;
+    let mut sub_metrics = HashMap::new();
+    let mut instance_families = HashMap::new();
+    // Fields declaring each name & key constant within this scope
+    let mut names: HashMap<String, &syn::Field> = HashMap::new();
+    let mut constants: HashMap<String, &syn::Field> = HashMap::new();
+    // Newtypes around a sub-scope are transparent, unless explicitly named
+    let is_newtype = matches!(&input.data, Data::Struct(data) if matches!(data.fields, Fields::Unnamed(_)))
+        && struct_data.fields.len() == 1;
+    for field in &struct_data.fields {
+        if !field.attributes.is_hidden() && is_dynamic_registry(field.ty) {
+            if root.is_none() || matches!(field.ty, Type::Array(_)) {
+                return Err(Error::new_spanned(
+                    field.original,
+                    "A dynamic registry can only be declared on a root",
+                ));
+            }
+            if let Some((first, _)) = dynamic.replace((field.original, field.member())) {
+                return Err(duplicate_error(
+                    field.original,
+                    first,
+                    "A root can only declare a single dynamic registry",
+                ));
+            }
+            if let Type::Path(path) = field.ty {
+                other_fields.insert(field.member(), TypePath::from(&path.path).full_path());
+            }
+            continue;
+        }
+        if !field.attributes.is_hidden() && is_unknown_keys(field.ty) {
+            if root.is_none() || matches!(field.ty, Type::Array(_)) {
+                return Err(Error::new_spanned(
+                    field.original,
+                    "Unknown keys can only be counted by a root",
+                ));
+            }
+            if let Some((first, _)) = unknown_keys.replace((field.original, field.member())) {
+                return Err(duplicate_error(
+                    field.original,
+                    first,
+                    "A root can only count its unknown keys once",
+                ));
+            }
+            if let Type::Path(path) = field.ty {
+                other_fields.insert(field.member(), TypePath::from(&path.path).full_path());
+            }
+            continue;
+        }
+        if !field.attributes.is_hidden() {
+            let name = field.get_metric().ok_or_else(|| {
+                Error::new_spanned(
+                    field.original,
+                    format!("No metric name for {}", field.member()),
+                )
+            })?;
+            check_name(&name, field.name_tokens())?;
+            for (label, tokens) in field.label_names() {
+                check_label(&label, tokens)?;
+            }
+            if let Some(first) = names.insert(name.clone(), field.original) {
+                return Err(duplicate_error(
+                    field.original,
+                    first,
+                    &format!("Duplicate metric name `{}`", name),
+                ));
+            }
+            let (ty, array) = match field.ty {
+                Type::Array(array) => (
+                    &*array.elem,
+                    Some(MetricArray {
+                        len: parse_array_len(&array.len)?,
+                        index_label: field
+                            .get_index_label()
+                            .map_or_else(|| DEFAULT_INDEX_LABEL.to_string(), |label| label.value()),
+                    }),
                 ),
-            ));
-        }
-
-        let mut name_override = None;
-        if let Attributes::Root(root) = &struct_data.attributes {
-            self.root_scope.get_or_insert(struct_data.ident.to_string());
-            self.key_separator = root
-                .separator
-                .as_deref()
-                .unwrap_or(DEFAULT_SEPARATOR)
-                .to_string();
-            name_override = root.name_override.clone();
-        }
-
-        let mut metrics = vec![];
-        let mut other_fields = HashMap::new();
-        let mut sub_metrics = HashMap::new();
-        for field in &struct_data.fields {
-            if !field.attributes.is_hidden() {
-                let name = field.get_metric().ok_or_else(|| {
-                    Error::new_spanned(
-                        &input,
-                        format!(
-                            "No metric name for {}",
-                            field.original.ident.as_ref().expect("No field name")
-                        ),
-                    )
-                })?;
-                let path = if let Type::Path(path) = field.ty {
-                    path
-                } else {
-                    return Err(Error::new_spanned(&input, "Invalid type for metrics"));
-                };
+                _ if field.get_index_label().is_some() => {
+                    return Err(Error::new_spanned(
+                        field.get_index_label(),
+                        "An index label can only be specified on an array",
+                    ));
+                }
+                ty => (ty, None),
+            };
+            let path = if let Type::Path(path) = ty {
+                path
+            } else {
+                return Err(Error::new_spanned(ty, "Invalid type for metrics"));
+            };
 
-                let type_path = TypePath::from(&path.path);
-                let ident = &path
-                    .path
-                    .segments
-                    .iter()
-                    .last()
-                    .ok_or_else(|| Error::new_spanned(&input, "Field needs to be a named type"))?
-                    .ident;
+            let type_path = TypePath::from(&path.path);
+            let segment = path
+                .path
+                .segments
+                .iter()
+                .last()
+                .ok_or_else(|| Error::new_spanned(path, "Field needs to be a named type"))?;
+            let ident = &segment.ident;
+            let family = parse_family(segment)?;
 
-                match MetricType::try_from(ident) {
-                    Ok(metric_type) => metrics.push(MetricInstance {
+            let unit = field
+                .get_unit()
+                .map(|unit| {
+                    if UNITS.contains(&unit.value().as_str()) {
+                        Ok(unit.value())
+                    } else {
+                        Err(Error::new_spanned(
+                            &unit.lit,
+                            format!(
+                                "Unknown unit: {}, expected one of {}",
+                                unit.value(),
+                                UNITS.join(", ")
+                            ),
+                        ))
+                    }
+                })
+                .transpose()?;
+
+            let metric_type = match (&family, field.get_kind()) {
+                (Some(_), Some(kind)) => {
+                    return Err(Error::new_spanned(
+                        kind,
+                        "The kind of a metric family is determined by its metric type",
+                    ));
+                }
+                (Some((metric_type, _)), None) => Ok(*metric_type),
+                (None, Some(kind)) => {
+                    Ok(MetricType::from_kind(&kind.value()).ok_or_else(|| {
+                        Error::new_spanned(
+                            &kind.lit,
+                            format!(
+                                "Unknown metric kind: {}, expected one of {}",
+                                kind.value(),
+                                MetricType::KINDS.join(", ")
+                            ),
+                        )
+                    })?)
+                }
+                (None, None) => MetricType::try_from(ident),
+            };
+            match metric_type {
+                Ok(_) if family.is_some() && array.is_some() => {
+                    return Err(Error::new_spanned(
+                        field.ty,
+                        "Arrays of metric families are not supported",
+                    ));
+                }
+                Ok(_) if field.get_instance_label().is_some() => {
+                    return Err(Error::new_spanned(
+                        field.get_instance_label(),
+                        "An instance label can only be specified on a sub-scope",
+                    ));
+                }
+                Ok(_) if field.route_tokens().is_some() => {
+                    return Err(Error::new_spanned(
+                        field.route_tokens(),
+                        "A route can only be specified on a sub-scope",
+                    ));
+                }
+                Ok(metric_type) => {
+                    let key = name.to_ascii_uppercase();
+                    if let Some(first) = constants.insert(key.clone(), field.original) {
+                        return Err(duplicate_error(
+                            field.original,
+                            first,
+                            &format!("Duplicate key constant `{}`", key),
+                        ));
+                    }
+                    metrics.push(MetricInstance {
                         key: name.to_ascii_uppercase(),
                         name: name.clone(),
                         type_path,
-                        instance: field
-                            .original
-                            .ident
-                            .as_ref()
-                            .ok_or_else(|| Error::new_spanned(field.original, "No field identity"))?
-                            .to_string(),
+                        instance: field.member(),
                         metric_type,
+                        description: field.get_description(),
+                        unit,
+                        labels: field.get_labels(),
+                        array,
+                        family: family.map(|(_, key)| key),
+                        custom: field.get_kind().is_some(),
+                        original: field.original.clone(),
+                        position: field.index,
                         hidden: field.attributes.is_hidden(),
-                    }),
-                    Err(_err) => {
-                        // Should be a subtype
-                        let orig = field.original;
-                        let field_type = if let Type::Path(path) = &orig.ty {
-                            path.path
-                                .get_ident()
-                                .ok_or_else(|| Error::new_spanned(&input, "Not valid field type"))?
-                                .clone()
-                        } else {
-                            return Err(Error::new_spanned(&input, "Only structs are supported"));
+                    })
+                }
+                Err(_err) => {
+                    // Should be a subtype, described by the scope macro sharing its name
+                    let orig = field.original;
+                    let instance_label = field.get_instance_label().map(AttributeValue::value);
+                    let route = field.get_route();
+                    if array.is_some() && (instance_label.is_some() || !route.is_empty()) {
+                        let attribute = match field.get_instance_label() {
+                            Some(label) => label.to_token_stream(),
+                            None => field.route_tokens().unwrap_or_default(),
                         };
-                        sub_metrics.insert(
-                            orig.ident.as_ref().expect("No identity").to_string(),
-                            SubMetric {
-                                ident: field_type.to_string(),
-                                hidden: field.attributes.is_hidden(),
-                            },
-                        );
+                        return Err(Error::new_spanned(
+                            attribute,
+                            "An array of sub-scopes is already labelled by its index, an instance label or route cannot be specified",
+                        ));
+                    }
+                    if instance_label.is_some() && !route.is_empty() {
+                        return Err(Error::new_spanned(
+                            field.get_instance_label(),
+                            "A routed sub-scope is already labelled by its route, an instance label cannot be specified",
+                        ));
+                    }
+                    // Routed sub-scopes are rendered as a single family, within the parent scope
+                    let family = if route.is_empty() {
+                        instance_label.clone()
+                    } else {
+                        Some(String::new())
+                    };
+                    if let Some(label) = &family {
+                        let family_type = instance_families
+                            .entry(label.clone())
+                            .or_insert_with(|| type_path.full_path());
+                        if *family_type != type_path.full_path() {
+                            return Err(Error::new_spanned(
+                                orig,
+                                if route.is_empty() {
+                                    format!(
+                                        "Sub-scopes sharing the instance label `{}` should be of the same type, expected {}",
+                                        label, family_type
+                                    )
+                                } else {
+                                    format!(
+                                        "Routed sub-scopes should be of the same type, expected {}",
+                                        family_type
+                                    )
+                                },
+                            ));
+                        }
                     }
+                    let mut macro_path = path.path.clone();
+                    if let Some(last) = macro_path.segments.last_mut() {
+                        last.arguments = PathArguments::None;
+                    }
+                    let name = if is_newtype && array.is_none() && !field.has_name_override() {
+                        String::new()
+                    } else {
+                        name
+                    };
+                    sub_metrics.insert(
+                        name,
+                        SubMetric {
+                            member: field.member(),
+                            position: field.index,
+                            ident: type_path.full_path(),
+                            ty: field.ty.clone(),
+                            macro_path,
+                            labels: field.get_labels(),
+                            instance_label,
+                            route,
+                            array,
+                            hidden: field.attributes.is_hidden(),
+                        },
+                    );
                 }
-            } else {
-                let orig = field.original;
-                let field_type = if let Type::Path(path) = &orig.ty {
-                    path.path
-                        .get_ident()
-                        .ok_or_else(|| Error::new_spanned(&input, "Not valid field type"))?
-                        .clone()
-                } else {
-                    return Err(Error::new_spanned(&input, "Only structs are supported"));
-                };
-                other_fields.insert(
-                    orig.ident.as_ref().expect("No identity").to_string(),
-                    field_type.to_string(),
-                );
             }
+        } else {
+            let orig = field.original;
+            let field_type = if let Type::Path(path) = &orig.ty {
+                TypePath::from(&path.path)
+            } else {
+                return Err(Error::new_spanned(
+                    &orig.ty,
+                    "A skipped field should be of a named type, initialised by its `new` constructor",
+                ));
+            };
+            other_fields.insert(field.member(), field_type.full_path());
         }
-        let scope = MetricScope {
-            struct_name: struct_data.ident.to_string(),
-            name_override,
-            metrics,
-            sub_metrics,
-            other_fields,
-        };
+    }
+    if let (Some((field, _)), Some((registry, _))) = (&unknown_keys, &dynamic) {
+        let mut error = Error::new_spanned(
+            field,
+            "Unknown keys are created in the dynamic registry of the root, they can't be counted",
+        );
+        error.combine(Error::new_spanned(
+            registry,
+            "dynamic registry declared here",
+        ));
+        return Err(error);
+    }
+    Ok(MetricScope {
+        struct_name: struct_data.ident.to_string(),
+        generics: input.generics.clone(),
+        root,
+        metrics,
+        sub_metrics,
+        other_fields,
+        dynamic: dynamic.map(|(_, member)| member),
+        unknown_keys: unknown_keys.map(|(_, member)| member),
+    })
+}
 
-        self.required_scopes.insert(scope.struct_name.clone());
-        self.required_scopes
-            .extend(scope.sub_metrics.iter().map(|(_, m)| &m.ident).cloned());
+/// Whether the field is a registry of metrics created at runtime, e.g. `DynamicRegistry<100>`
+fn is_dynamic_registry(ty: &Type) -> bool {
+    let ty = match ty {
+        Type::Array(array) => &*array.elem,
+        ty => ty,
+    };
+    matches!(ty, Type::Path(path) if path.path.segments.last().is_some_and(|last| last.ident == "DynamicRegistry"))
+}
 
-        self.scopes.insert(struct_data.ident.to_string(), scope);
+/// Whether the field counts the samples dropped for keys unknown to a root, i.e. `UnknownKeys`
+fn is_unknown_keys(ty: &Type) -> bool {
+    let ty = match ty {
+        Type::Array(array) => &*array.elem,
+        ty => ty,
+    };
+    matches!(ty, Type::Path(path) if path.path.segments.last().is_some_and(|last| last.ident == "UnknownKeys"))
+}
 
-        Ok(())
+/// Error pointing at both fields declaring the same name or key
+fn duplicate_error(duplicate: &syn::Field, first: &syn::Field, message: &str) -> Error {
+    let mut error = Error::new_spanned(duplicate, message);
+    error.combine(Error::new_spanned(first, "first declared here"));
+    error
+}
+
+/// Metric type & key of a metric family, e.g. `CounterFamily<Status>`
+fn parse_family(segment: &PathSegment) -> Result<Option<(MetricType, Path)>> {
+    let metric_type = match segment.ident.to_string().as_str() {
+        "CounterFamily" => MetricType::Counter,
+        "GaugeFamily" => MetricType::Gauge,
+        "DiscreteGaugeFamily" => MetricType::DiscreteGauge,
+        "HistogramFamily" => MetricType::Histogram,
+        _ => return Ok(None),
+    };
+    match &segment.arguments {
+        PathArguments::AngleBracketed(arguments) => match arguments.args.first() {
+            Some(GenericArgument::Type(Type::Path(key))) => {
+                Ok(Some((metric_type, key.path.clone())))
+            }
+            _ => Err(Error::new_spanned(
+                arguments,
+                "A metric family should be keyed by a `FamilyKey` type",
+            )),
+        },
+        _ => Err(Error::new_spanned(
+            segment,
+            "A metric family should be keyed by a `FamilyKey` type",
+        )),
+    }
+}
+
+/// Length of an array of metrics or sub-scopes, which needs to be known to generate its keys
+fn parse_array_len(len: &Expr) -> Result<usize> {
+    let invalid = || {
+        Error::new_spanned(
+            len,
+            "The length of an array of metrics should be a non-zero integer literal",
+        )
+    };
+    match len {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(lit), ..
+        }) => match lit.base10_parse()? {
+            0 => Err(invalid()),
+            len => Ok(len),
+        },
+        _ => Err(invalid()),
     }
 }
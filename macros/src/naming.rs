@@ -0,0 +1,64 @@
+use proc_macro2::{Ident, Span};
+use quote::ToTokens;
+use syn::{Error, Result};
+
+/// Whether a name is valid as a segment of a key & as its key constant, i.e. `[a-zA-Z_][a-zA-Z0-9_]*`
+fn is_valid_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Validate the name of a metric or sub-scope
+pub fn check_name(name: &str, tokens: impl ToTokens) -> Result<()> {
+    if is_valid_name(name) {
+        Ok(())
+    } else {
+        Err(Error::new_spanned(
+            tokens,
+            format!(
+                "Invalid metric name `{}`, expected a name matching `[a-zA-Z_][a-zA-Z0-9_]*`",
+                name
+            ),
+        ))
+    }
+}
+
+/// Identifier of the accessor of a key, i.e. its lowercase name, as a raw identifier when it's a keyword.
+/// Names without letters, e.g. `_1`, are suffixed to distinguish them from their key constant.
+pub fn accessor_ident(name: &str) -> Ident {
+    let name = name.to_ascii_lowercase();
+    match name.as_str() {
+        _ if !name.chars().any(|c| c.is_ascii_alphabetic()) => {
+            Ident::new(&format!("{}_", name), Span::call_site())
+        }
+        // Keywords which cannot be raw identifiers
+        "self" | "super" | "crate" => Ident::new(&format!("{}_", name), Span::call_site()),
+        _ if syn::parse_str::<Ident>(&name).is_ok() => Ident::new(&name, Span::call_site()),
+        _ => Ident::new_raw(&name, Span::call_site()),
+    }
+}
+
+/// Validate the name of a rendered label.
+/// Prometheus label names should match `[a-zA-Z_][a-zA-Z0-9_]*`, names starting with `__` are reserved.
+#[cfg(feature = "prometheus")]
+pub fn check_label(label: &str, tokens: impl ToTokens) -> Result<()> {
+    if is_valid_name(label) && !label.starts_with("__") {
+        Ok(())
+    } else {
+        Err(Error::new_spanned(
+            tokens,
+            format!(
+                "Invalid label name `{}`, expected a name matching `[a-zA-Z_][a-zA-Z0-9_]*` not starting with `__`",
+                label
+            ),
+        ))
+    }
+}
+
+#[cfg(not(feature = "prometheus"))]
+pub fn check_label(_label: &str, _tokens: impl ToTokens) -> Result<()> {
+    Ok(())
+}
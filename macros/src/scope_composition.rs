@@ -0,0 +1,242 @@
+use crate::ast::{Attributes, Struct};
+use crate::metric_tree::MetricTree;
+use proc_macro2::{Ident, Span, TokenStream};
+use quote::{format_ident, quote, ToTokens};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{braced, bracketed, Data, DeriveInput, Error, LitStr, Path, Result, Token, Visibility};
+
+const SCOPE_MARKER: &str = "scope";
+const PENDING_MARKER: &str = "pending";
+const RESOLVED_MARKER: &str = "resolved";
+
+/// Description of a scope & all of its (nested) sub-scopes.
+/// Every `Catalogue` struct exposes its description through a scope macro sharing the struct's name,
+/// which parent scopes also pass their references to the sub-scope through, as `@scope` expressions.
+pub struct ScopeDescription {
+    pub input: DeriveInput,
+    /// Descriptions of the sub-scopes, by their key segment
+    pub sub_scopes: Vec<(LitStr, ScopeDescription)>,
+}
+
+impl Parse for ScopeDescription {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let content;
+        braced!(content in input);
+        let derive_input = content.parse()?;
+        let content;
+        bracketed!(content in input);
+        let sub_scopes = Punctuated::<SubScopeDescription, Token![,]>::parse_terminated(&content)?
+            .into_iter()
+            .map(|sub| (sub.field, sub.description))
+            .collect();
+        Ok(ScopeDescription {
+            input: derive_input,
+            sub_scopes,
+        })
+    }
+}
+
+impl ToTokens for ScopeDescription {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let input = &self.input;
+        let sub_scopes = self
+            .sub_scopes
+            .iter()
+            .map(|(field, description)| quote! { #field => #description });
+        tokens.extend(quote! { { #input } [ #(#sub_scopes),* ] });
+    }
+}
+
+struct SubScopeDescription {
+    field: LitStr,
+    description: ScopeDescription,
+}
+
+impl Parse for SubScopeDescription {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let field = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let description = input.parse()?;
+        Ok(SubScopeDescription { field, description })
+    }
+}
+
+struct PendingSubScope {
+    field: LitStr,
+    path: Path,
+}
+
+impl Parse for PendingSubScope {
+    fn parse(input: ParseStream) -> Result<Self> {
+        let field = input.parse()?;
+        input.parse::<Token![=>]>()?;
+        let path = input.call(Path::parse_mod_style)?;
+        Ok(PendingSubScope { field, path })
+    }
+}
+
+/// State of composing a scope description, by invoking the scope macro of each sub-scope in turn.
+/// Each scope macro is invoked from the module of the parent, where its path is known to resolve,
+/// and responds by appending its own description as the `resolved` sub-scope.
+pub struct ScopeComposition {
+    scope: ScopeDescription,
+    pending: Vec<PendingSubScope>,
+    resolved: Option<ScopeDescription>,
+}
+
+impl ScopeComposition {
+    pub fn new(input: DeriveInput, sub_scopes: Vec<(String, Path)>) -> Self {
+        Self {
+            scope: ScopeDescription {
+                input,
+                sub_scopes: vec![],
+            },
+            pending: sub_scopes
+                .into_iter()
+                .map(|(field, path)| PendingSubScope {
+                    field: LitStr::new(&field, Span::call_site()),
+                    path,
+                })
+                .collect(),
+            resolved: None,
+        }
+    }
+
+    /// Start composing the scope, as part of the derived implementation
+    pub fn start(&self) -> TokenStream {
+        let state = self.state();
+        quote! {
+            ::metrics_catalogue::compose_scope! { #state }
+        }
+    }
+
+    /// Resolve the next sub-scope, or generate the scope macro & catalogue once all sub-scopes are resolved
+    pub fn generate(mut self) -> Result<TokenStream> {
+        if let Some(resolved) = self.resolved.take() {
+            let pending = self.pending.remove(0);
+            self.scope.sub_scopes.push((pending.field, resolved));
+        }
+
+        if let Some(next) = self.pending.first() {
+            let path = &next.path;
+            let state = self.state();
+            return Ok(quote! {
+                #path! { #state }
+            });
+        }
+
+        let scope_macro = self.generate_scope_macro();
+        let catalogue = match &self.scope.input.data {
+            Data::Struct(data) => {
+                let root = Struct::from_syn(&self.scope.input, data)?;
+                if matches!(root.attributes, Attributes::Root(_)) {
+                    MetricTree::from_description(&self.scope)?.generate_catalogue()?
+                } else {
+                    quote! {}
+                }
+            }
+            // Family keys only describe their variants
+            Data::Enum(_) => quote! {},
+            Data::Union(data) => {
+                return Err(Error::new_spanned(
+                    data.union_token,
+                    "Metrics are only supported as structs",
+                ))
+            }
+        };
+
+        Ok(quote! {
+            #scope_macro
+
+            #catalogue
+        })
+    }
+
+    fn state(&self) -> TokenStream {
+        let scope = &self.scope;
+        let pending = self.pending.iter().map(|PendingSubScope { field, path }| {
+            quote! { #field => #path }
+        });
+        quote! {
+            scope = #scope,
+            pending = [ #(#pending),* ],
+        }
+    }
+
+    fn generate_scope_macro(&self) -> TokenStream {
+        let ident = &self.scope.input.ident;
+        let vis = &self.scope.input.vis;
+        let description = &self.scope;
+
+        // Public scopes are exported, allowing them to be embedded into catalogues of other crates.
+        // Exported macros share the crate root, so the name needs to be unique within the crate:
+        // identical structs declared in different modules are distinguished by the location of their name.
+        let (macro_name, export) = if matches!(vis, Visibility::Public(_)) {
+            let mut hasher = DefaultHasher::new();
+            description.to_token_stream().to_string().hash(&mut hasher);
+            format!("{:?}", ident.span()).hash(&mut hasher);
+            (
+                format_ident!("__metrics_catalogue_{}_{:x}", ident, hasher.finish()),
+                quote! { #[macro_export] },
+            )
+        } else {
+            (format_ident!("__metrics_catalogue_{}", ident), quote! {})
+        };
+
+        quote! {
+            #[doc(hidden)]
+            #export
+            macro_rules! #macro_name {
+                (@scope $scope:expr) => { $scope };
+                ($($composition:tt)*) => {
+                    ::metrics_catalogue::compose_scope! { $($composition)* resolved = #description }
+                };
+            }
+
+            #[doc(hidden)]
+            #[allow(unused_imports)]
+            #vis use #macro_name as #ident;
+        }
+    }
+}
+
+impl Parse for ScopeComposition {
+    fn parse(input: ParseStream) -> Result<Self> {
+        parse_marker(input, SCOPE_MARKER)?;
+        let scope = input.parse()?;
+        input.parse::<Token![,]>()?;
+
+        parse_marker(input, PENDING_MARKER)?;
+        let content;
+        bracketed!(content in input);
+        let pending = Punctuated::<PendingSubScope, Token![,]>::parse_terminated(&content)?
+            .into_iter()
+            .collect();
+        input.parse::<Token![,]>()?;
+
+        let resolved = if input.is_empty() {
+            None
+        } else {
+            parse_marker(input, RESOLVED_MARKER)?;
+            Some(input.parse()?)
+        };
+
+        Ok(ScopeComposition {
+            scope,
+            pending,
+            resolved,
+        })
+    }
+}
+
+fn parse_marker(input: ParseStream, marker: &str) -> Result<()> {
+    let ident = input.parse::<Ident>()?;
+    if ident != marker {
+        return Err(Error::new_spanned(ident, format!("Expected `{}`", marker)));
+    }
+    input.parse::<Token![=]>()?;
+    Ok(())
+}
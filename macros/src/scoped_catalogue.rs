@@ -1,26 +1,105 @@
-use proc_macro2::TokenStream;
+use proc_macro2::{Ident, TokenStream};
 use quote::{format_ident, quote};
 use std::collections::HashMap;
 
 pub struct ScopedCatalogue {
     pub mod_name: String,
-    pub metrics: Vec<(String, String)>,
+    /// Length of the array of this scope within its parent, if any
+    pub len: Option<usize>,
+    pub metrics: Vec<(String, String, Option<usize>)>,
+    pub families: Vec<FamilyCatalogue>,
     pub sub_scopes: HashMap<String, ScopedCatalogue>,
+    /// Accessors of the metrics of this scope
+    pub accessors: Vec<TokenStream>,
+    /// Descriptors of all keys of a root catalogue
+    pub descriptors: Option<Vec<TokenStream>>,
+    /// Trait implemented by the root of a catalogue, locating the metrics of its keys
+    pub root: Option<TokenStream>,
+}
+
+/// Keys of a metric family, within the module sharing the name of the family
+pub struct FamilyCatalogue {
+    pub name: String,
+    /// Key constants & label values of the keys
+    pub keys: Vec<(Ident, String)>,
+    /// Accessors of the members of the family
+    pub accessors: Vec<TokenStream>,
 }
 
 impl ScopedCatalogue {
-    pub fn generate_prefix_keys(&self, prefix: &str, separator: &str) -> TokenStream {
-        let metric_keys = self.metrics.iter().map(|(k, v)| {
+    pub fn generate_prefix_keys(&self, prefix: &KeyPrefix, separator: &str) -> TokenStream {
+        let metric_keys = self.metrics.iter().map(|(k, v, len)| {
             let key = format_ident!("{}", k);
-            let name = format!("{}{}", prefix, v);
-            let kv = quote! { #key: &str = #name };
+            let (ty, name) = prefix
+                .map(&|prefix| match len {
+                    Some(len) => KeyPrefix::Array(
+                        (0..*len)
+                            .map(|i| {
+                                KeyPrefix::Single(format!("{}{}{}{}", prefix, v, separator, i))
+                            })
+                            .collect(),
+                    ),
+                    None => KeyPrefix::Single(format!("{}{}", prefix, v)),
+                })
+                .tokens();
+            let kv = quote! { #key: #ty = #name };
             quote! { pub const #kv; }
         });
+        let family_keys = self.families.iter().map(|family| {
+            let FamilyCatalogue {
+                name,
+                keys,
+                accessors,
+            } = family;
+            let keys = keys.iter().map(|(key, value)| {
+                let (ty, value) = prefix
+                    .map(&|prefix| {
+                        KeyPrefix::Single(format!("{}{}{}{}", prefix, name, separator, value))
+                    })
+                    .tokens();
+                quote! { pub const #key: #ty = #value; }
+            });
+            let name_mod = format_ident!("{}", name);
+            quote! {
+                pub mod #name_mod {
+                    #(#keys)*
+
+                    #(#accessors)*
+                }
+            }
+        });
         let sub_metric_spaces = self.sub_scopes.iter().map(|(name, scope)| {
-            let prefix = format!("{}{}{}", prefix, name, separator);
+            let prefix = prefix.map(&|prefix| match scope.len {
+                Some(len) => KeyPrefix::Array(
+                    (0..len)
+                        .map(|i| {
+                            KeyPrefix::Single(format!(
+                                "{}{}{}{}{}",
+                                prefix, name, separator, i, separator
+                            ))
+                        })
+                        .collect(),
+                ),
+                // Transparent newtypes share the keys of their parent
+                None if name.is_empty() => KeyPrefix::Single(prefix.to_string()),
+                None => KeyPrefix::Single(format!("{}{}{}", prefix, name, separator)),
+            });
             scope.generate_prefix_keys(&prefix, separator)
         });
-        let keys = metric_keys.chain(sub_metric_spaces);
+        let descriptors = self.descriptors.as_ref().map(|descriptors| {
+            quote! {
+                pub const METRICS: &[::metrics_catalogue::MetricDescriptor] = &[#(#descriptors),*];
+            }
+        });
+        let keys = metric_keys
+            .chain(family_keys)
+            .chain(sub_metric_spaces)
+            .chain(descriptors)
+            .chain(self.root.clone())
+            .chain(self.accessors.iter().cloned());
+        if self.mod_name.is_empty() {
+            return quote! { #(#keys)* };
+        }
         let name_mod = format_ident!("{}", self.mod_name);
         quote! {
             #[allow(non_camel_case_types)]
@@ -30,3 +109,34 @@ impl ScopedCatalogue {
         }
     }
 }
+
+/// Prefix of the keys within a scope.
+/// Keys within arrays of sub-scopes are generated as arrays, indexed like the sub-scopes.
+pub enum KeyPrefix {
+    Single(String),
+    Array(Vec<KeyPrefix>),
+}
+
+impl KeyPrefix {
+    fn map(&self, f: &dyn Fn(&str) -> KeyPrefix) -> KeyPrefix {
+        match self {
+            KeyPrefix::Single(prefix) => f(prefix),
+            KeyPrefix::Array(prefixes) => {
+                KeyPrefix::Array(prefixes.iter().map(|prefix| prefix.map(f)).collect())
+            }
+        }
+    }
+
+    /// Type & value of the keys
+    fn tokens(&self) -> (TokenStream, TokenStream) {
+        match self {
+            KeyPrefix::Single(key) => (quote! { &str }, quote! { #key }),
+            KeyPrefix::Array(keys) => {
+                let len = keys.len();
+                let (ty, keys): (Vec<_>, Vec<_>) = keys.iter().map(KeyPrefix::tokens).unzip();
+                let ty = ty.into_iter().next().unwrap_or_else(|| quote! { &str });
+                (quote! { [#ty; #len] }, quote! { [#(#keys),*] })
+            }
+        }
+    }
+}
@@ -0,0 +1,233 @@
+#[cfg(feature = "prometheus")]
+use crate::prometheus::StringRender;
+use crate::{
+    CounterMetric, GaugeMetric, GaugeValue, HistogramMetric, Key, Label, MetricRef, MetricVisitor,
+    Recorder, Registry, Unit, UnknownKeys,
+};
+
+/// Catalogue which can be mounted in a [`CompositeRegistry`], looked up & rendered under its prefix
+#[cfg(feature = "prometheus")]
+pub trait Mountable: Registry + StringRender {}
+
+#[cfg(feature = "prometheus")]
+impl<T: Registry + StringRender> Mountable for T {}
+
+/// Catalogue which can be mounted in a [`CompositeRegistry`], looked up under its prefix
+#[cfg(not(feature = "prometheus"))]
+pub trait Mountable: Registry {}
+
+#[cfg(not(feature = "prometheus"))]
+impl<T: Registry> Mountable for T {}
+
+/// Registry combining several catalogues, each mounted under a prefix, e.g. `"app"` or `"db"`.
+/// The keys of a catalogue are prefixed by its mount prefix & the separator of the composite,
+/// a catalogue mounted under an empty prefix keeping its own keys.
+/// Samples of keys unknown to all catalogues are forwarded to the recorder of the root mounted under
+/// their prefix, if it records them, and counted by the composite otherwise.
+/// The misses of the composite & its catalogues are rendered as a single family.
+pub struct CompositeRegistry {
+    pub(crate) separator: &'static str,
+    mounts: Vec<(String, Box<dyn Mountable + Send + Sync>)>,
+    pub(crate) unknown_keys: UnknownKeys,
+}
+
+impl Default for CompositeRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CompositeRegistry {
+    pub fn new() -> Self {
+        Self::with_separator(".")
+    }
+
+    /// Composite joining mount prefixes & keys with the provided separator, which its catalogues should share
+    pub fn with_separator(separator: &'static str) -> Self {
+        Self {
+            separator,
+            mounts: vec![],
+            unknown_keys: UnknownKeys::new(),
+        }
+    }
+
+    /// Mounts a catalogue under the provided prefix.
+    /// Catalogues are looked up in their mount order, the first one resolving a key recording it.
+    pub fn mount(
+        mut self,
+        prefix: impl Into<String>,
+        catalogue: impl Mountable + Send + Sync + 'static,
+    ) -> Self {
+        self.mounts.push((prefix.into(), Box::new(catalogue)));
+        self
+    }
+
+    /// Mounted catalogues & their prefix, in their mount order
+    #[cfg(feature = "prometheus")]
+    pub(crate) fn mounts(&self) -> impl Iterator<Item = (&str, &dyn Mountable)> {
+        self.mounts
+            .iter()
+            .map(|(prefix, catalogue)| (prefix.as_str(), catalogue.as_ref() as &dyn Mountable))
+    }
+
+    /// Recorder of the catalogue whose prefix matches a key none of them resolves, along with the key
+    /// relative to that prefix. Catalogues mounted under an empty prefix aren't forwarded any key.
+    fn forward(&self, key: &Key) -> Option<(&dyn Recorder, Key)> {
+        self.mounts
+            .iter()
+            .filter(|(prefix, _)| !prefix.is_empty())
+            .find_map(|(prefix, catalogue)| {
+                let name = key
+                    .name()
+                    .strip_prefix(prefix.as_str())?
+                    .strip_prefix(self.separator)?;
+                let recorder = catalogue.recorder()?;
+                Some((recorder, Key::from_parts(name.to_string(), key.labels())))
+            })
+    }
+
+    /// First look-up resolved by a catalogue whose prefix matches the key, relative to that prefix
+    fn resolve<'a, T>(
+        &'a self,
+        name: &str,
+        find: impl Fn(&'a dyn Mountable, &str) -> Option<T>,
+    ) -> Option<T> {
+        self.mounts.iter().find_map(|(prefix, catalogue)| {
+            let name = if prefix.is_empty() {
+                name
+            } else {
+                name.strip_prefix(prefix.as_str())?
+                    .strip_prefix(self.separator)?
+            };
+            find(catalogue.as_ref(), name)
+        })
+    }
+}
+
+impl Registry for CompositeRegistry {
+    fn find_counter(&self, name: &str) -> Option<&dyn CounterMetric> {
+        self.resolve(name, |catalogue, name| catalogue.find_counter(name))
+    }
+
+    fn find_gauge(&self, name: &str) -> Option<&dyn GaugeMetric> {
+        self.resolve(name, |catalogue, name| catalogue.find_gauge(name))
+    }
+
+    fn find_histogram(&self, name: &str) -> Option<&dyn HistogramMetric> {
+        self.resolve(name, |catalogue, name| catalogue.find_histogram(name))
+    }
+
+    fn find_description(&self, name: &str) -> Option<&'static str> {
+        self.resolve(name, |catalogue, name| catalogue.find_description(name))
+    }
+
+    fn find_unit(&self, name: &str) -> Option<Unit> {
+        self.resolve(name, |catalogue, name| catalogue.find_unit(name))
+    }
+
+    fn route_counter(&self, name: &str, labels: &[Label]) -> Option<&dyn CounterMetric> {
+        self.resolve(name, |catalogue, name| {
+            catalogue.route_counter(name, labels)
+        })
+    }
+
+    fn route_gauge(&self, name: &str, labels: &[Label]) -> Option<&dyn GaugeMetric> {
+        self.resolve(name, |catalogue, name| catalogue.route_gauge(name, labels))
+    }
+
+    fn route_histogram(&self, name: &str, labels: &[Label]) -> Option<&dyn HistogramMetric> {
+        self.resolve(name, |catalogue, name| {
+            catalogue.route_histogram(name, labels)
+        })
+    }
+
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        for (prefix, catalogue) in &self.mounts {
+            if prefix.is_empty() {
+                catalogue.visit(visitor);
+            } else {
+                catalogue.visit(&mut |key: &str, metric: MetricRef<'_>| {
+                    visitor.visit(&format!("{}{}{}", prefix, self.separator, key), metric)
+                });
+            }
+        }
+    }
+
+    fn unknown_keys(&self) -> Option<&UnknownKeys> {
+        Some(&self.unknown_keys)
+    }
+
+    fn recorder(&self) -> Option<&dyn Recorder> {
+        Some(self)
+    }
+}
+
+impl Recorder for CompositeRegistry {
+    // Keys of the catalogues are already registered, other keys are registered by their recorder
+    fn register_counter(&self, key: &Key, unit: Option<Unit>, description: Option<&'static str>) {
+        if self
+            .route_counter(key.name(), key.labels().as_slice())
+            .is_none()
+        {
+            if let Some((recorder, key)) = self.forward(key) {
+                recorder.register_counter(&key, unit, description);
+            }
+        }
+    }
+
+    fn register_gauge(&self, key: &Key, unit: Option<Unit>, description: Option<&'static str>) {
+        if self
+            .route_gauge(key.name(), key.labels().as_slice())
+            .is_none()
+        {
+            if let Some((recorder, key)) = self.forward(key) {
+                recorder.register_gauge(&key, unit, description);
+            }
+        }
+    }
+
+    fn register_histogram(&self, key: &Key, unit: Option<Unit>, description: Option<&'static str>) {
+        if self
+            .route_histogram(key.name(), key.labels().as_slice())
+            .is_none()
+        {
+            if let Some((recorder, key)) = self.forward(key) {
+                recorder.register_histogram(&key, unit, description);
+            }
+        }
+    }
+
+    fn increment_counter(&self, key: &Key, value: u64) {
+        match self.route_counter(key.name(), key.labels().as_slice()) {
+            Some(metric) => metric.increment(value),
+            None => match self.forward(key) {
+                Some((recorder, key)) => recorder.increment_counter(&key, value),
+                None => self.unknown_keys.record(key.name()),
+            },
+        }
+    }
+
+    fn update_gauge(&self, key: &Key, value: GaugeValue) {
+        match self.route_gauge(key.name(), key.labels().as_slice()) {
+            Some(metric) => match value {
+                GaugeValue::Increment(val) => metric.increase(val),
+                GaugeValue::Decrement(val) => metric.decrease(val),
+                GaugeValue::Absolute(val) => metric.set(val),
+            },
+            None => match self.forward(key) {
+                Some((recorder, key)) => recorder.update_gauge(&key, value),
+                None => self.unknown_keys.record(key.name()),
+            },
+        }
+    }
+
+    fn record_histogram(&self, key: &Key, value: f64) {
+        match self.route_histogram(key.name(), key.labels().as_slice()) {
+            Some(metric) => metric.insert(value),
+            None => match self.forward(key) {
+                Some((recorder, key)) => recorder.record_histogram(&key, value),
+                None => self.unknown_keys.record(key.name()),
+            },
+        }
+    }
+}
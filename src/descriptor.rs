@@ -0,0 +1,19 @@
+use crate::{MetricKind, Unit};
+
+/// Description of a single key of a catalogue, allowing the catalogue to be enumerated at runtime.
+/// Every root generates the descriptors of all its keys as the `METRICS` constant of its catalogue module.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MetricDescriptor {
+    /// Key of the metric, as used by the [`Recorder`](crate::Recorder)
+    pub key: &'static str,
+    /// Kind of the metric
+    pub kind: MetricKind,
+    /// Unit of the metric, if declared
+    pub unit: Option<Unit>,
+    /// Description of the metric, if declared
+    pub description: Option<&'static str>,
+    /// Static labels the metric is rendered with, including the ones inherited from its parent scopes
+    pub labels: &'static [(&'static str, &'static str)],
+    /// Path of the metric's field from the root, e.g. `shards[0].requests`
+    pub path: &'static str,
+}
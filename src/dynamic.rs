@@ -0,0 +1,177 @@
+use crate::{
+    Counter, Gauge, GaugeMetric, GaugeValue, Histogram, HistogramMetric, Key, MetricKind, Recorder,
+    Unit,
+};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::RwLock;
+
+/// Registry of metrics created at runtime, for keys which aren't part of the catalogue,
+/// e.g. the ones recorded by other crates.
+/// Declared as a field of a root, it records & renders all keys the root doesn't know.
+/// At most `CAPACITY` keys are created, any further keys are dropped to avoid a cardinality explosion.
+/// Histograms retain their samples for `RETENTION` seconds.
+pub struct DynamicRegistry<const CAPACITY: usize, const RETENTION: u64 = 60> {
+    families: RwLock<BTreeMap<String, DynamicFamily<RETENTION>>>,
+    len: AtomicUsize,
+}
+
+/// Metrics sharing a name, distinguished by their labels
+pub(crate) struct DynamicFamily<const RETENTION: u64> {
+    pub(crate) kind: MetricKind,
+    pub(crate) unit: Option<Unit>,
+    pub(crate) description: Option<&'static str>,
+    pub(crate) metrics: BTreeMap<Vec<(String, String)>, DynamicMetric<RETENTION>>,
+}
+
+pub(crate) enum DynamicMetric<const RETENTION: u64> {
+    Counter(Counter),
+    Gauge(Gauge),
+    Histogram(Histogram<RETENTION>),
+}
+
+impl<const RETENTION: u64> DynamicMetric<RETENTION> {
+    fn new(kind: MetricKind) -> Self {
+        match kind {
+            MetricKind::Counter => DynamicMetric::Counter(Counter::new()),
+            MetricKind::Gauge => DynamicMetric::Gauge(Gauge::new()),
+            MetricKind::Histogram => DynamicMetric::Histogram(Histogram::new()),
+        }
+    }
+}
+
+impl<const CAPACITY: usize, const RETENTION: u64> Default for DynamicRegistry<CAPACITY, RETENTION> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const CAPACITY: usize, const RETENTION: u64> DynamicRegistry<CAPACITY, RETENTION> {
+    pub const fn new() -> Self {
+        Self {
+            families: RwLock::new(BTreeMap::new()),
+            len: AtomicUsize::new(0),
+        }
+    }
+
+    /// Number of keys created in the registry, distinguished by their name & labels
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Relaxed)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Calls `f` with each family of metrics sharing a name, ordered by name
+    pub(crate) fn for_each_family(&self, mut f: impl FnMut(&str, &DynamicFamily<RETENTION>)) {
+        let families = self.families.read().unwrap_or_else(|err| err.into_inner());
+        for (name, family) in families.iter() {
+            f(name, family);
+        }
+    }
+
+    /// Calls `f` with the metric of the key, creating it if it doesn't exist yet.
+    /// Keys sharing the name of a metric of another kind, or exceeding the capacity, are dropped.
+    fn with_metric(
+        &self,
+        key: &Key,
+        kind: MetricKind,
+        unit: Option<Unit>,
+        description: Option<&'static str>,
+        f: impl FnOnce(&DynamicMetric<RETENTION>),
+    ) {
+        let labels = key
+            .labels()
+            .map(|label| (label.key().to_string(), label.value().to_string()))
+            .collect::<Vec<_>>();
+        {
+            let families = self.families.read().unwrap_or_else(|err| err.into_inner());
+            if let Some(family) = families.get(key.name()) {
+                if family.kind != kind {
+                    return;
+                }
+                if let Some(metric) = family.metrics.get(&labels) {
+                    if unit.is_none() && description.is_none() {
+                        f(metric);
+                        return;
+                    }
+                }
+            }
+        }
+
+        let mut families = self.families.write().unwrap_or_else(|err| err.into_inner());
+        let exists = families
+            .get(key.name())
+            .is_some_and(|family| family.metrics.contains_key(&labels));
+        if !exists && self.len() >= CAPACITY {
+            return;
+        }
+        let family = families
+            .entry(key.name().to_string())
+            .or_insert_with(|| DynamicFamily {
+                kind,
+                unit: None,
+                description: None,
+                metrics: BTreeMap::new(),
+            });
+        if family.kind != kind {
+            return;
+        }
+        if unit.is_some() {
+            family.unit = unit;
+        }
+        if description.is_some() {
+            family.description = description;
+        }
+        let metric = family.metrics.entry(labels).or_insert_with(|| {
+            self.len.fetch_add(1, Ordering::Relaxed);
+            DynamicMetric::new(kind)
+        });
+        f(metric);
+    }
+}
+
+impl<const CAPACITY: usize, const RETENTION: u64> Recorder
+    for DynamicRegistry<CAPACITY, RETENTION>
+{
+    fn register_counter(&self, key: &Key, unit: Option<Unit>, description: Option<&'static str>) {
+        self.with_metric(key, MetricKind::Counter, unit, description, |_| {})
+    }
+
+    fn register_gauge(&self, key: &Key, unit: Option<Unit>, description: Option<&'static str>) {
+        self.with_metric(key, MetricKind::Gauge, unit, description, |_| {})
+    }
+
+    fn register_histogram(&self, key: &Key, unit: Option<Unit>, description: Option<&'static str>) {
+        self.with_metric(key, MetricKind::Histogram, unit, description, |_| {})
+    }
+
+    fn increment_counter(&self, key: &Key, value: u64) {
+        self.with_metric(key, MetricKind::Counter, None, None, |metric| {
+            if let DynamicMetric::Counter(counter) = metric {
+                counter.increment(value);
+            }
+        })
+    }
+
+    fn update_gauge(&self, key: &Key, value: GaugeValue) {
+        self.with_metric(key, MetricKind::Gauge, None, None, |metric| {
+            if let DynamicMetric::Gauge(gauge) = metric {
+                match value {
+                    GaugeValue::Increment(val) => gauge.increase(val),
+                    GaugeValue::Decrement(val) => gauge.decrease(val),
+                    GaugeValue::Absolute(val) => gauge.set(val),
+                }
+            }
+        })
+    }
+
+    fn record_histogram(&self, key: &Key, value: f64) {
+        self.with_metric(key, MetricKind::Histogram, None, None, |metric| {
+            if let DynamicMetric::Histogram(histogram) = metric {
+                histogram.insert(value);
+            }
+        })
+    }
+}
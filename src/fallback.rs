@@ -0,0 +1,84 @@
+use crate::{GaugeValue, Key, Recorder, Registry, Unit};
+
+/// Recorder of a catalogue, forwarding the keys it doesn't know to another recorder,
+/// e.g. an exporter recording the metrics of other crates.
+pub struct FallbackRecorder<C, F> {
+    catalogue: C,
+    fallback: F,
+}
+
+impl<C: Registry, F: Recorder> FallbackRecorder<C, F> {
+    pub const fn new(catalogue: C, fallback: F) -> Self {
+        Self {
+            catalogue,
+            fallback,
+        }
+    }
+
+    /// Catalogue recording its own keys
+    pub fn catalogue(&self) -> &C {
+        &self.catalogue
+    }
+
+    /// Recorder of the keys unknown to the catalogue
+    pub fn fallback(&self) -> &F {
+        &self.fallback
+    }
+}
+
+impl<C: Registry, F: Recorder> Recorder for FallbackRecorder<C, F> {
+    fn register_counter(&self, key: &Key, unit: Option<Unit>, description: Option<&'static str>) {
+        let labels = key.labels().as_slice();
+        if self.catalogue.route_counter(key.name(), labels).is_none() {
+            self.fallback.register_counter(key, unit, description);
+        }
+    }
+
+    fn register_gauge(&self, key: &Key, unit: Option<Unit>, description: Option<&'static str>) {
+        let labels = key.labels().as_slice();
+        if self.catalogue.route_gauge(key.name(), labels).is_none() {
+            self.fallback.register_gauge(key, unit, description);
+        }
+    }
+
+    fn register_histogram(&self, key: &Key, unit: Option<Unit>, description: Option<&'static str>) {
+        let labels = key.labels().as_slice();
+        if self.catalogue.route_histogram(key.name(), labels).is_none() {
+            self.fallback.register_histogram(key, unit, description);
+        }
+    }
+
+    fn increment_counter(&self, key: &Key, value: u64) {
+        match self
+            .catalogue
+            .route_counter(key.name(), key.labels().as_slice())
+        {
+            Some(metric) => metric.increment(value),
+            None => self.fallback.increment_counter(key, value),
+        }
+    }
+
+    fn update_gauge(&self, key: &Key, value: GaugeValue) {
+        match self
+            .catalogue
+            .route_gauge(key.name(), key.labels().as_slice())
+        {
+            Some(metric) => match value {
+                GaugeValue::Increment(val) => metric.increase(val),
+                GaugeValue::Decrement(val) => metric.decrease(val),
+                GaugeValue::Absolute(val) => metric.set(val),
+            },
+            None => self.fallback.update_gauge(key, value),
+        }
+    }
+
+    fn record_histogram(&self, key: &Key, value: f64) {
+        match self
+            .catalogue
+            .route_histogram(key.name(), key.labels().as_slice())
+        {
+            Some(metric) => metric.insert(value),
+            None => self.fallback.record_histogram(key, value),
+        }
+    }
+}
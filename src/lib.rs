@@ -1,14 +1,38 @@
+mod composite;
+mod descriptor;
+mod dynamic;
+mod fallback;
 mod metrics;
 #[cfg(feature = "prometheus")]
 pub mod prometheus;
 mod registry;
+mod unknown;
 
 /// Export metrics types
 pub use crate::metrics::*;
 /// Re-export metrics crate
 #[doc(hidden)]
 pub use ::metrics::*;
+/// Export the registry combining several catalogues
+pub use composite::{CompositeRegistry, Mountable};
+/// Export descriptors of the generated catalogues
+pub use descriptor::MetricDescriptor;
+/// Export the registry of keys unknown to a catalogue
+pub use dynamic::DynamicRegistry;
+/// Export the recorder forwarding keys unknown to a catalogue
+pub use fallback::FallbackRecorder;
+/// Re-export scope composition, used by the generated code
+#[doc(hidden)]
+pub use metrics_catalogue_macros::compose_scope;
 /// Re-export declarative macros
-pub use metrics_catalogue_macros::Catalogue;
+pub use metrics_catalogue_macros::{Catalogue, FamilyKey};
+/// Re-export perfect hash maps, used by the generated look-up of keys
+#[doc(hidden)]
+pub use phf;
+/// Export look-up of array elements, projection of fields & resolution of keys, used by the generated code
+#[doc(hidden)]
+pub use registry::{metric_index, resolve_kind, scope_index, unresolved_key, ScopeField};
 /// Export registry trait
-pub use registry::Registry;
+pub use registry::{MetricRef, MetricVisitor, Registry, ScopedRegistry};
+/// Export the samples dropped for keys unknown to a catalogue
+pub use unknown::UnknownKeys;
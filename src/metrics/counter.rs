@@ -3,6 +3,12 @@ use std::sync::atomic::{AtomicU64, Ordering};
 /// Mimics a [`metrics-core`] monotonically increasing [`Counter`] type
 pub struct Counter(AtomicU64);
 
+impl Default for Counter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Counter {
     pub const fn new() -> Self {
         Self(AtomicU64::new(0))
@@ -20,3 +26,22 @@ impl Counter {
         self.0.load(Ordering::Relaxed)
     }
 }
+
+pub trait CounterMetric {
+    /// Increases the value of the counter by a discrete amount
+    fn increment(&self, val: u64);
+    /// Read the current state of the counter
+    fn read(&self) -> u64;
+}
+
+impl CounterMetric for Counter {
+    #[inline]
+    fn increment(&self, val: u64) {
+        Counter::increment(self, val)
+    }
+
+    #[inline]
+    fn read(&self) -> u64 {
+        Counter::read(self)
+    }
+}
@@ -0,0 +1,123 @@
+use crate::{Counter, DiscreteGauge, Gauge, Histogram, Label};
+use std::marker::PhantomData;
+use std::ops::Index;
+
+/// Key of a metric [`Family`], typically an enum of unit variants deriving [`FamilyKey`]
+pub trait FamilyKey: Sized + 'static {
+    /// Name of the label distinguishing the members of the family
+    const LABEL: &'static str;
+    /// Label values of all keys, in declaration order
+    const VALUES: &'static [&'static str];
+    /// Fixed-size array holding a metric per key
+    type Array<M: ConstDefault>: FamilyArray<M>;
+
+    /// Position of the key within [`FamilyKey::VALUES`]
+    fn index(&self) -> usize;
+}
+
+/// Const initialiser of a metric, allowing a [`Family`] to be const initialised
+pub trait ConstDefault {
+    const DEFAULT: Self;
+}
+
+impl ConstDefault for Counter {
+    const DEFAULT: Self = Counter::new();
+}
+
+impl ConstDefault for Gauge {
+    const DEFAULT: Self = Gauge::new();
+}
+
+impl ConstDefault for DiscreteGauge {
+    const DEFAULT: Self = DiscreteGauge::new();
+}
+
+impl<const RETENTION: u64> ConstDefault for Histogram<RETENTION> {
+    const DEFAULT: Self = Histogram::new();
+}
+
+/// Storage of the metrics of a [`Family`]
+pub trait FamilyArray<M>: AsRef<[M]> {
+    const ARRAY: Self;
+}
+
+impl<M: ConstDefault, const N: usize> FamilyArray<M> for [M; N] {
+    const ARRAY: Self = [M::DEFAULT; N];
+}
+
+/// Family of metrics, holding a metric per key & rendered as a single metric distinguished by a label
+pub struct Family<K: FamilyKey, M: ConstDefault> {
+    metrics: K::Array<M>,
+    _key: PhantomData<fn() -> K>,
+}
+
+/// Family of [`Counter`]s
+pub type CounterFamily<K> = Family<K, Counter>;
+/// Family of [`Gauge`]s
+pub type GaugeFamily<K> = Family<K, Gauge>;
+/// Family of [`DiscreteGauge`]s
+pub type DiscreteGaugeFamily<K> = Family<K, DiscreteGauge>;
+/// Family of [`Histogram`]s
+pub type HistogramFamily<K, const RETENTION: u64> = Family<K, Histogram<RETENTION>>;
+
+impl<K: FamilyKey, M: ConstDefault> Default for Family<K, M> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: FamilyKey, M: ConstDefault> Family<K, M> {
+    pub const fn new() -> Self {
+        Self {
+            metrics: <K::Array<M> as FamilyArray<M>>::ARRAY,
+            _key: PhantomData,
+        }
+    }
+
+    /// Metric of the provided key
+    #[inline]
+    pub fn get(&self, key: K) -> &M {
+        &self.metrics.as_ref()[key.index()]
+    }
+
+    /// Metric of the key with the provided label value
+    pub fn find(&self, value: &str) -> Option<&M> {
+        K::VALUES
+            .iter()
+            .position(|v| *v == value)
+            .map(|index| &self.metrics.as_ref()[index])
+    }
+
+    /// Metric at the provided index, in the order of the label values of the key
+    #[doc(hidden)]
+    pub fn get_index(&self, index: usize) -> Option<&M> {
+        self.metrics.as_ref().get(index)
+    }
+
+    /// All metrics of the family, along with the label value of their key
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, &M)> {
+        K::VALUES.iter().copied().zip(self.metrics.as_ref())
+    }
+
+    /// Find a metric relative to the name of the family,
+    /// either as `{separator}value` or by the label of the family
+    #[doc(hidden)]
+    pub fn find_key(&self, key: &str, labels: &[Label], separator: &str) -> Option<&M> {
+        if key.is_empty() {
+            let label = labels.iter().find(|label| label.key() == K::LABEL)?;
+            self.find(label.value())
+        } else {
+            self.find(key.strip_prefix(separator)?)
+        }
+    }
+}
+
+/// Metric at the provided index, in the order of the label values of the key
+impl<K: FamilyKey, M: ConstDefault> Index<usize> for Family<K, M> {
+    type Output = M;
+
+    #[inline]
+    fn index(&self, index: usize) -> &M {
+        &self.metrics.as_ref()[index]
+    }
+}
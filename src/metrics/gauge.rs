@@ -4,6 +4,12 @@ use std::sync::atomic::{AtomicU64, Ordering};
 /// Limit granularity to discrete values, for real units, please use [`Gauge`]
 pub struct DiscreteGauge(AtomicU64);
 
+impl Default for DiscreteGauge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl DiscreteGauge {
     pub const fn new() -> Self {
         Self(AtomicU64::new(0))
@@ -50,6 +56,12 @@ impl GaugeMetric for DiscreteGauge {
 /// Limit granularity to real values, for discrete units, please use [`DiscreteGauge`]
 pub struct Gauge(AtomicU64);
 
+impl Default for Gauge {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[allow(dead_code)]
 impl Gauge {
     pub const fn new() -> Self {
@@ -63,8 +75,8 @@ impl Gauge {
         let mut old = self.0.load(Ordering::Relaxed);
         loop {
             if let Err(previous) = self.0.compare_exchange_weak(
-                f(f64::from_bits(old)).to_bits(),
                 old,
+                f(f64::from_bits(old)).to_bits(),
                 Ordering::SeqCst,
                 Ordering::Relaxed,
             ) {
@@ -19,6 +19,12 @@ pub struct Histogram<const RETENTION: u64> {
     started: AtomicU64,
 }
 
+impl<const RETENTION: u64> Default for Histogram<RETENTION> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl<const RETENTION: u64> Histogram<RETENTION> {
     pub const fn new() -> Self {
         Self {
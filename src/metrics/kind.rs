@@ -0,0 +1,35 @@
+use crate::{ConstDefault, Counter, DiscreteGauge, Gauge, Histogram};
+
+/// Kind of a metric, determining how it is looked up, recorded & rendered
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum MetricKind {
+    /// Looked up as a [`CounterMetric`](crate::CounterMetric)
+    Counter,
+    /// Looked up as a [`GaugeMetric`](crate::GaugeMetric)
+    Gauge,
+    /// Looked up as a [`HistogramMetric`](crate::HistogramMetric)
+    Histogram,
+}
+
+/// A metric type which can be declared within a catalogue.
+/// Types other than the provided metrics are declared using their kind, e.g. `#[metric(kind = "counter")]`,
+/// and should implement the metric trait matching their kind.
+pub trait CatalogueMetric: ConstDefault {
+    const KIND: MetricKind;
+}
+
+impl CatalogueMetric for Counter {
+    const KIND: MetricKind = MetricKind::Counter;
+}
+
+impl CatalogueMetric for Gauge {
+    const KIND: MetricKind = MetricKind::Gauge;
+}
+
+impl CatalogueMetric for DiscreteGauge {
+    const KIND: MetricKind = MetricKind::Gauge;
+}
+
+impl<const RETENTION: u64> CatalogueMetric for Histogram<RETENTION> {
+    const KIND: MetricKind = MetricKind::Histogram;
+}
@@ -1,7 +1,11 @@
 mod counter;
+mod family;
 mod gauge;
 mod histogram;
+mod kind;
 
 pub use counter::*;
+pub use family::*;
 pub use gauge::*;
 pub use histogram::*;
+pub use kind::*;
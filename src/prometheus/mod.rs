@@ -1,7 +1,17 @@
-use crate::prometheus::utils::{write_metric_line, write_type_line, Label};
-use crate::{Counter, DiscreteGauge, Gauge, GaugeMetric, Histogram, HistogramMetric};
+use crate::dynamic::DynamicMetric;
+use crate::prometheus::utils::{
+    write_help_line, write_metric_line, write_type_line, write_unit_line, Label,
+};
+use crate::{
+    CompositeRegistry, ConstDefault, Counter, CounterMetric, DiscreteGauge, DynamicRegistry,
+    Family, FamilyKey, Gauge, GaugeMetric, Histogram, HistogramMetric, MetricKind, Unit,
+    UnknownKeys,
+};
 use metrics_util::Summary;
-use std::iter::{empty, once};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::iter::once;
 
 mod server;
 mod utils;
@@ -12,6 +22,131 @@ pub const QUANTILES: [f64; 4] = [0.0, 0.5, 0.9, 0.99];
 
 pub trait StringRender {
     fn render(&self, prefix: &str, name: &str, s: &mut String);
+
+    /// Renders the metrics when mounted in a [`CompositeRegistry`], leaving the samples dropped
+    /// for unknown keys to the composite, which merges those of all its catalogues
+    #[doc(hidden)]
+    fn render_mounted(&self, prefix: &str, name: &str, s: &mut String) {
+        self.render(prefix, name, s)
+    }
+}
+
+/// Instance of a scope, along with the static labels inherited from its parent scopes
+pub type ScopeInstance<'a, S> = (&'a S, Vec<(&'static str, &'static str)>);
+
+/// Renders all metrics within a single scope, using the key separator provided by the root.
+/// Static labels are inherited from all parent scopes.
+pub trait ScopedRender {
+    fn render_scoped(
+        &self,
+        prefix: &str,
+        separator: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    ) where
+        Self: Sized,
+    {
+        Self::render_instances(&[(self, labels.to_vec())], prefix, separator, s)
+    }
+
+    /// Renders several instances of the scope as a single family per metric, distinguished by their labels
+    fn render_instances(
+        instances: &[ScopeInstance<Self>],
+        prefix: &str,
+        separator: &str,
+        s: &mut String,
+    ) where
+        Self: Sized,
+    {
+        Self::render_instances_except(instances, prefix, separator, &[], s)
+    }
+
+    /// Renders several instances of the scope, except its metrics sharing one of the provided names,
+    /// rendered along with the metrics of the parent scope they are routed from
+    fn render_instances_except(
+        instances: &[ScopeInstance<Self>],
+        prefix: &str,
+        separator: &str,
+        except: &[&str],
+        s: &mut String,
+    ) where
+        Self: Sized;
+
+    /// Renders the values of the metric of several instances of the scope under the provided name, if any,
+    /// without its header
+    fn render_values(
+        instances: &[ScopeInstance<Self>],
+        prefix: &str,
+        separator: &str,
+        name: &str,
+        s: &mut String,
+    ) where
+        Self: Sized;
+}
+
+/// Metadata of a single metric, as declared in the catalogue
+#[derive(Debug, Default, Clone)]
+pub struct MetricMetadata<'a> {
+    /// Description of the metric, rendered as its `# HELP` line
+    pub description: Option<&'a str>,
+    /// Unit of the metric, rendered as its OpenMetrics `# UNIT` line
+    pub unit: Option<Unit>,
+    /// Static labels of the metric, rendered with each of its values
+    pub labels: &'a [(&'static str, &'static str)],
+}
+
+/// Renders a single metric, including its metadata
+pub trait MetricRender {
+    /// Renders the metadata & type of the metric family
+    fn render_header(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String);
+    /// Renders the values of the metric, distinguished by the provided labels
+    fn render_values(
+        &self,
+        prefix: &str,
+        name: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    );
+
+    fn render_metric(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String) {
+        self.render_header(prefix, name, metadata, s);
+        self.render_values(prefix, name, metadata.labels, s);
+        s.push('\n');
+    }
+}
+
+#[inline]
+fn static_labels<'a>(
+    labels: &'a [(&'static str, &'static str)],
+) -> impl Iterator<Item = Label<'a, String>> + Clone + 'a {
+    labels
+        .iter()
+        .map(|(key, value)| Label::KeyValue((*key, value.to_string())))
+}
+
+#[inline]
+fn write_metadata(s: &mut String, prefix: &str, name: &str, metadata: &MetricMetadata) {
+    if let Some(desc) = metadata.description {
+        write_help_line(s, prefix, name, desc);
+    }
+    if let Some(unit) = &metadata.unit {
+        write_unit_line(s, prefix, name, unit.as_str());
+    }
+}
+
+/// Name of a family with a unit, suffixed by it as required by its `# UNIT` line, unless it already is
+fn unit_name<'a>(name: &'a str, unit: Option<&Unit>) -> Cow<'a, str> {
+    match unit {
+        // Characters other than letters, digits & `:` are rendered as `_`
+        Some(unit)
+            if !name.strip_suffix(unit.as_str()).is_some_and(|name| {
+                name.ends_with(|c: char| !c.is_ascii_alphanumeric() && c != ':')
+            }) =>
+        {
+            Cow::Owned(format!("{}_{}", name, unit.as_str()))
+        }
+        _ => Cow::Borrowed(name),
+    }
 }
 
 impl<S: StringRender> StringRender for &S {
@@ -19,6 +154,11 @@ impl<S: StringRender> StringRender for &S {
     fn render(&self, prefix: &str, name: &str, s: &mut String) {
         <S as StringRender>::render(self, prefix, name, s)
     }
+
+    #[inline]
+    fn render_mounted(&self, prefix: &str, name: &str, s: &mut String) {
+        <S as StringRender>::render_mounted(self, prefix, name, s)
+    }
 }
 
 impl<S: StringRender> StringRender for std::sync::Arc<S> {
@@ -26,62 +166,121 @@ impl<S: StringRender> StringRender for std::sync::Arc<S> {
     fn render(&self, prefix: &str, name: &str, s: &mut String) {
         <S as StringRender>::render(self, prefix, name, s)
     }
+
+    #[inline]
+    fn render_mounted(&self, prefix: &str, name: &str, s: &mut String) {
+        <S as StringRender>::render_mounted(self, prefix, name, s)
+    }
 }
 
-impl StringRender for Counter {
+impl MetricRender for Counter {
     #[inline]
-    fn render(&self, prefix: &str, name: &str, s: &mut String) {
-        // TODO: Process description
-        // if let Some(desc) = descriptions.get(name.as_str()) {
-        //     write_help_line(&mut output, name.as_str(), desc);
-        // }
+    fn render_header(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String) {
+        write_metadata(s, prefix, name, metadata);
         write_type_line(s, prefix, name, "counter");
+    }
 
-        // TODO: process labels
-        let labels = empty();
-        write_metric_line::<&str, u64, _>(s, prefix, name, None, labels, self.read());
-        s.push('\n');
+    #[inline]
+    fn render_values(
+        &self,
+        prefix: &str,
+        name: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    ) {
+        write_metric_line(s, prefix, name, None, static_labels(labels), self.read());
+    }
+}
+
+impl StringRender for Counter {
+    #[inline]
+    fn render(&self, prefix: &str, name: &str, s: &mut String) {
+        self.render_metric(prefix, name, &MetricMetadata::default(), s)
     }
 }
 
 #[inline]
-fn render_gauge<G: GaugeMetric>(g: &G, prefix: &str, name: &str, s: &mut String) {
-    // TODO: Process description
-    // if let Some(desc) = descriptions.get(name.as_str()) {
-    //     write_help_line(&mut output, name.as_str(), desc);
-    // }
-
-    write_type_line(s, prefix, name, "gauge");
-    // TODO: process labels
-    let labels = empty();
-    write_metric_line::<&str, f64, _>(s, prefix, name, None, labels, g.read());
-    s.push('\n');
+fn render_gauge_values<G: GaugeMetric>(
+    g: &G,
+    prefix: &str,
+    name: &str,
+    labels: &[(&'static str, &'static str)],
+    s: &mut String,
+) {
+    write_metric_line(s, prefix, name, None, static_labels(labels), g.read());
+}
+
+impl MetricRender for Gauge {
+    #[inline]
+    fn render_header(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String) {
+        write_metadata(s, prefix, name, metadata);
+        write_type_line(s, prefix, name, "gauge");
+    }
+
+    #[inline]
+    fn render_values(
+        &self,
+        prefix: &str,
+        name: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    ) {
+        render_gauge_values(self, prefix, name, labels, s)
+    }
 }
 
 impl StringRender for Gauge {
     #[inline]
     fn render(&self, prefix: &str, name: &str, s: &mut String) {
-        render_gauge(self, prefix, name, s)
+        self.render_metric(prefix, name, &MetricMetadata::default(), s)
+    }
+}
+
+impl MetricRender for DiscreteGauge {
+    #[inline]
+    fn render_header(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String) {
+        write_metadata(s, prefix, name, metadata);
+        write_type_line(s, prefix, name, "gauge");
+    }
+
+    #[inline]
+    fn render_values(
+        &self,
+        prefix: &str,
+        name: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    ) {
+        render_gauge_values(self, prefix, name, labels, s)
     }
 }
 
 impl StringRender for DiscreteGauge {
     #[inline]
     fn render(&self, prefix: &str, name: &str, s: &mut String) {
-        render_gauge(self, prefix, name, s)
+        self.render_metric(prefix, name, &MetricMetadata::default(), s)
     }
 }
 
 #[inline]
-fn render_histogram<H: HistogramMetric>(h: &H, prefix: &str, name: &str, s: &mut String) {
-    // TODO: Process description
-    // if let Some(desc) = descriptions.get(name.as_str()) {
-    //     write_help_line(&mut output, name.as_str(), desc);
-    // }
-
-    write_type_line(s, prefix, name, "histogram");
-    // TODO: process labels
-    let labels = empty();
+fn render_histogram_values<H: HistogramMetric>(
+    h: &H,
+    prefix: &str,
+    name: &str,
+    labels: &[(&'static str, &'static str)],
+    s: &mut String,
+) {
+    write_histogram_lines(h, prefix, name, static_labels(labels), s)
+}
+
+#[inline]
+fn write_histogram_lines<'a, H: HistogramMetric + ?Sized, L: Display>(
+    h: &H,
+    prefix: &str,
+    name: &str,
+    labels: impl Iterator<Item = Label<'a, L>> + Clone,
+    s: &mut String,
+) {
     let mut summary = Summary::with_defaults();
     let samples = h.read();
     let mut sum = 0.0;
@@ -97,26 +296,250 @@ fn render_histogram<H: HistogramMetric>(h: &H, prefix: &str, name: &str, s: &mut
             prefix,
             name,
             None,
-            labels.clone().chain(once(Label::KeyValue(("quantile", q)))),
+            labels
+                .clone()
+                .map(|label| match label {
+                    Label::Key(key) => Label::Key(key),
+                    Label::KeyValue((key, value)) => Label::KeyValue((key, value.to_string())),
+                })
+                .chain(once(Label::KeyValue(("quantile", q.to_string())))),
             value,
         );
     }
-    write_metric_line(s, prefix, name, Some("sum"), empty::<Label<usize>>(), sum);
-    write_metric_line(
-        s,
-        prefix,
-        name,
-        Some("count"),
-        empty::<Label<usize>>(),
-        count as u64,
-    );
+    write_metric_line(s, prefix, name, Some("sum"), labels.clone(), sum);
+    write_metric_line(s, prefix, name, Some("count"), labels, count as u64);
+}
 
-    s.push('\n');
+impl<const RETENTION: u64> MetricRender for Histogram<RETENTION> {
+    #[inline]
+    fn render_header(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String) {
+        write_metadata(s, prefix, name, metadata);
+        write_type_line(s, prefix, name, "histogram");
+    }
+
+    #[inline]
+    fn render_values(
+        &self,
+        prefix: &str,
+        name: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    ) {
+        render_histogram_values(self, prefix, name, labels, s)
+    }
 }
 
 impl<const RETENTION: u64> StringRender for Histogram<RETENTION> {
     #[inline]
     fn render(&self, prefix: &str, name: &str, s: &mut String) {
-        render_histogram(self, prefix, name, s)
+        self.render_metric(prefix, name, &MetricMetadata::default(), s)
+    }
+}
+
+/// Renders a user-defined counter, declared using `#[metric(kind = "counter")]`
+#[doc(hidden)]
+pub struct AsCounter<'a, M>(pub &'a M);
+
+impl<M: CounterMetric> MetricRender for AsCounter<'_, M> {
+    #[inline]
+    fn render_header(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String) {
+        write_metadata(s, prefix, name, metadata);
+        write_type_line(s, prefix, name, "counter");
+    }
+
+    #[inline]
+    fn render_values(
+        &self,
+        prefix: &str,
+        name: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    ) {
+        write_metric_line(s, prefix, name, None, static_labels(labels), self.0.read());
+    }
+}
+
+/// Renders a user-defined gauge, declared using `#[metric(kind = "gauge")]`
+#[doc(hidden)]
+pub struct AsGauge<'a, M>(pub &'a M);
+
+impl<M: GaugeMetric> MetricRender for AsGauge<'_, M> {
+    #[inline]
+    fn render_header(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String) {
+        write_metadata(s, prefix, name, metadata);
+        write_type_line(s, prefix, name, "gauge");
+    }
+
+    #[inline]
+    fn render_values(
+        &self,
+        prefix: &str,
+        name: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    ) {
+        render_gauge_values(self.0, prefix, name, labels, s)
+    }
+}
+
+/// Renders a user-defined histogram, declared using `#[metric(kind = "histogram")]`
+#[doc(hidden)]
+pub struct AsHistogram<'a, M>(pub &'a M);
+
+impl<M: HistogramMetric> MetricRender for AsHistogram<'_, M> {
+    #[inline]
+    fn render_header(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String) {
+        write_metadata(s, prefix, name, metadata);
+        write_type_line(s, prefix, name, "histogram");
+    }
+
+    #[inline]
+    fn render_values(
+        &self,
+        prefix: &str,
+        name: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    ) {
+        render_histogram_values(self.0, prefix, name, labels, s)
+    }
+}
+
+impl<K: FamilyKey, M: ConstDefault + MetricRender> MetricRender for Family<K, M> {
+    #[inline]
+    fn render_header(&self, prefix: &str, name: &str, metadata: &MetricMetadata, s: &mut String) {
+        if let Some((_, metric)) = self.iter().next() {
+            metric.render_header(prefix, name, metadata, s);
+        }
+    }
+
+    fn render_values(
+        &self,
+        prefix: &str,
+        name: &str,
+        labels: &[(&'static str, &'static str)],
+        s: &mut String,
+    ) {
+        for (value, metric) in self.iter() {
+            metric.render_values(prefix, name, &[labels, &[(K::LABEL, value)]].concat(), s);
+        }
+    }
+}
+
+impl<K: FamilyKey, M: ConstDefault + MetricRender> StringRender for Family<K, M> {
+    #[inline]
+    fn render(&self, prefix: &str, name: &str, s: &mut String) {
+        self.render_metric(prefix, name, &MetricMetadata::default(), s)
+    }
+}
+
+impl<const CAPACITY: usize, const RETENTION: u64> StringRender
+    for DynamicRegistry<CAPACITY, RETENTION>
+{
+    fn render(&self, prefix: &str, _name: &str, s: &mut String) {
+        self.for_each_family(|name, family| {
+            let name = &*unit_name(name, family.unit.as_ref());
+            let metadata = MetricMetadata {
+                description: family.description,
+                unit: family.unit.clone(),
+                labels: &[],
+            };
+            write_metadata(s, prefix, name, &metadata);
+            let metric_type = match family.kind {
+                MetricKind::Counter => "counter",
+                MetricKind::Gauge => "gauge",
+                MetricKind::Histogram => "histogram",
+            };
+            write_type_line(s, prefix, name, metric_type);
+            for (labels, metric) in &family.metrics {
+                let labels = labels
+                    .iter()
+                    .map(|(key, value)| Label::KeyValue((key.as_str(), value)));
+                match metric {
+                    DynamicMetric::Counter(counter) => {
+                        write_metric_line(s, prefix, name, None, labels, counter.read())
+                    }
+                    DynamicMetric::Gauge(gauge) => {
+                        write_metric_line(s, prefix, name, None, labels, gauge.read())
+                    }
+                    DynamicMetric::Histogram(histogram) => {
+                        write_histogram_lines(histogram, prefix, name, labels, s)
+                    }
+                }
+            }
+            s.push('\n');
+        });
+    }
+}
+
+/// Name of the counter of samples dropped for keys unknown to a catalogue, labelled by their key
+pub const UNKNOWN_KEY_TOTAL: &str = "catalogue_unknown_key_total";
+
+impl StringRender for UnknownKeys {
+    fn render(&self, _prefix: &str, _name: &str, s: &mut String) {
+        render_unknown_keys(once(("", self)), "", s);
+    }
+}
+
+/// Renders the samples dropped for unknown keys by several catalogues as a single family,
+/// the keys of each catalogue being prefixed by its mount prefix & the separator,
+/// followed by the samples dropped for the keys beyond their capacity, if any
+fn render_unknown_keys<'a>(
+    unknown_keys: impl Iterator<Item = (&'a str, &'a UnknownKeys)>,
+    separator: &str,
+    s: &mut String,
+) {
+    let mut keys = BTreeMap::new();
+    let mut overflow = 0;
+    for (prefix, unknown_keys) in unknown_keys {
+        unknown_keys.for_each(|key, misses| {
+            if key.is_empty() {
+                overflow += misses;
+            } else if prefix.is_empty() {
+                *keys.entry(key.to_string()).or_insert(0) += misses;
+            } else {
+                *keys
+                    .entry(format!("{}{}{}", prefix, separator, key))
+                    .or_insert(0) += misses;
+            }
+        });
+    }
+    if keys.is_empty() && overflow == 0 {
+        return;
+    }
+    write_help_line(
+        s,
+        "",
+        UNKNOWN_KEY_TOTAL,
+        "Samples dropped for keys unknown to the catalogue",
+    );
+    write_type_line(s, "", UNKNOWN_KEY_TOTAL, "counter");
+    let overflow = (overflow > 0).then(|| (String::new(), overflow));
+    for (key, misses) in keys.into_iter().chain(overflow) {
+        let labels = once(Label::KeyValue(("key", &key)));
+        write_metric_line(s, "", UNKNOWN_KEY_TOTAL, None, labels, misses);
+    }
+    s.push('\n');
+}
+
+impl StringRender for CompositeRegistry {
+    fn render(&self, prefix: &str, name: &str, s: &mut String) {
+        self.render_mounted(prefix, name, s);
+        let mounted = self.mounts().filter_map(|(prefix, catalogue)| {
+            catalogue
+                .unknown_keys()
+                .map(|unknown_keys| (prefix, unknown_keys))
+        });
+        render_unknown_keys(
+            once(("", &self.unknown_keys)).chain(mounted),
+            self.separator,
+            s,
+        );
+    }
+
+    fn render_mounted(&self, _prefix: &str, _name: &str, s: &mut String) {
+        for (prefix, catalogue) in self.mounts() {
+            catalogue.render_mounted("", prefix, s);
+        }
     }
 }
@@ -1,31 +1,63 @@
 use std::fmt::Display;
 
-// pub(super) fn write_help_line(buffer: &mut String, name: &str, desc: &str) {
-//     buffer.push_str("# HELP ");
-//     buffer.push_str(name);
-//     buffer.push(' ');
-//     buffer.push_str(desc);
-//     buffer.push('\n');
-// }
+/// Writes the name of a metric, replacing the characters invalid in a Prometheus name,
+/// e.g. the default `.` separator, by `_`, & prefixing a leading digit by `_`
+#[inline]
+fn write_name(buffer: &mut String, prefix: &str, name: &str) {
+    for (index, c) in prefix.chars().chain(name.chars()).enumerate() {
+        match c {
+            'a'..='z' | 'A'..='Z' | '_' | ':' => buffer.push(c),
+            '0'..='9' if index > 0 => buffer.push(c),
+            '0'..='9' => {
+                buffer.push('_');
+                buffer.push(c);
+            }
+            _ => buffer.push('_'),
+        }
+    }
+}
+
+#[inline]
+pub(super) fn write_help_line(buffer: &mut String, prefix: &str, name: &str, desc: &str) {
+    buffer.push_str("# HELP ");
+    write_name(buffer, prefix, name);
+    buffer.push(' ');
+    for c in desc.chars() {
+        match c {
+            '\\' => buffer.push_str("\\\\"),
+            '\n' => buffer.push_str("\\n"),
+            c => buffer.push(c),
+        }
+    }
+    buffer.push('\n');
+}
+
+#[inline]
+pub(super) fn write_unit_line(buffer: &mut String, prefix: &str, name: &str, unit: &str) {
+    buffer.push_str("# UNIT ");
+    write_name(buffer, prefix, name);
+    buffer.push(' ');
+    buffer.push_str(unit);
+    buffer.push('\n');
+}
 
-pub enum Label<T: Display> {
+pub enum Label<'a, T: Display> {
     #[allow(dead_code)]
     Key(String),
-    KeyValue((&'static str, T)),
+    KeyValue((&'a str, T)),
 }
 
 #[inline]
 pub(super) fn write_type_line(buffer: &mut String, prefix: &str, name: &str, metric_type: &str) {
     buffer.push_str("# TYPE ");
-    buffer.push_str(prefix);
-    buffer.push_str(name);
+    write_name(buffer, prefix, name);
     buffer.push(' ');
     buffer.push_str(metric_type);
     buffer.push('\n');
 }
 
 #[inline]
-pub(super) fn write_metric_line<L, V, LB>(
+pub(super) fn write_metric_line<'a, L, V, LB>(
     buffer: &mut String,
     prefix: &str,
     name: &str,
@@ -35,10 +67,9 @@ pub(super) fn write_metric_line<L, V, LB>(
 ) where
     L: Display,
     V: Display,
-    LB: Iterator<Item = Label<L>>,
+    LB: Iterator<Item = Label<'a, L>>,
 {
-    buffer.push_str(prefix);
-    buffer.push_str(name);
+    write_name(buffer, prefix, name);
     if let Some(suffix) = suffix {
         buffer.push('_');
         buffer.push_str(suffix)
@@ -59,7 +90,14 @@ pub(super) fn write_metric_line<L, V, LB>(
             Label::KeyValue((name, value)) => {
                 buffer.push_str(name);
                 buffer.push_str("=\"");
-                buffer.push_str(value.to_string().as_str());
+                for c in value.to_string().chars() {
+                    match c {
+                        '\\' => buffer.push_str("\\\\"),
+                        '"' => buffer.push_str("\\\""),
+                        '\n' => buffer.push_str("\\n"),
+                        c => buffer.push(c),
+                    }
+                }
                 buffer.push('"');
             }
         }
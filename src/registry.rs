@@ -1,10 +1,322 @@
-use crate::{Counter, GaugeMetric, HistogramMetric};
+use crate::{
+    CounterMetric, GaugeMetric, HistogramMetric, Key, Label, MetricKind, Recorder, Unit,
+    UnknownKeys,
+};
 
 pub trait Registry {
     /// Find a registered counter under the provided name
-    fn find_counter(&self, name: &str) -> Option<&Counter>;
+    fn find_counter(&self, name: &str) -> Option<&dyn CounterMetric>;
     /// Find a registered gauge under the provided name
     fn find_gauge(&self, name: &str) -> Option<&dyn GaugeMetric>;
     /// Find a registered [`Histogram`] under the provided name
     fn find_histogram(&self, name: &str) -> Option<&dyn HistogramMetric>;
+    /// Find the description of the metric registered under the provided name
+    fn find_description(&self, name: &str) -> Option<&'static str>;
+    /// Find the unit of the metric registered under the provided name
+    fn find_unit(&self, name: &str) -> Option<Unit>;
+    /// Find a registered counter under the provided name, selecting sub-scopes routed by the provided labels
+    fn route_counter(&self, name: &str, _labels: &[Label]) -> Option<&dyn CounterMetric> {
+        self.find_counter(name)
+    }
+    /// Find a registered gauge under the provided name, selecting sub-scopes routed by the provided labels
+    fn route_gauge(&self, name: &str, _labels: &[Label]) -> Option<&dyn GaugeMetric> {
+        self.find_gauge(name)
+    }
+    /// Find a registered [`Histogram`] under the provided name, selecting sub-scopes routed by the provided labels
+    fn route_histogram(&self, name: &str, _labels: &[Label]) -> Option<&dyn HistogramMetric> {
+        self.find_histogram(name)
+    }
+    /// Visit all registered metrics, along with their full key
+    fn visit(&self, visitor: &mut dyn MetricVisitor);
+    /// Samples dropped by the recorder of a root, for keys unknown to its catalogue
+    fn unknown_keys(&self) -> Option<&UnknownKeys> {
+        None
+    }
+    /// Recorder of a root recording the samples of the keys it doesn't resolve, forwarded those samples
+    /// when mounted in a [`CompositeRegistry`](crate::CompositeRegistry), e.g. to create them in its dynamic registry.
+    /// Misses forwarded to no recorder are counted by the composite.
+    fn recorder(&self) -> Option<&dyn Recorder> {
+        None
+    }
+}
+
+impl<R: Registry + ?Sized> Registry for &R {
+    #[inline]
+    fn find_counter(&self, name: &str) -> Option<&dyn CounterMetric> {
+        (**self).find_counter(name)
+    }
+    #[inline]
+    fn find_gauge(&self, name: &str) -> Option<&dyn GaugeMetric> {
+        (**self).find_gauge(name)
+    }
+    #[inline]
+    fn find_histogram(&self, name: &str) -> Option<&dyn HistogramMetric> {
+        (**self).find_histogram(name)
+    }
+    #[inline]
+    fn find_description(&self, name: &str) -> Option<&'static str> {
+        (**self).find_description(name)
+    }
+    #[inline]
+    fn find_unit(&self, name: &str) -> Option<Unit> {
+        (**self).find_unit(name)
+    }
+    #[inline]
+    fn route_counter(&self, name: &str, labels: &[Label]) -> Option<&dyn CounterMetric> {
+        (**self).route_counter(name, labels)
+    }
+    #[inline]
+    fn route_gauge(&self, name: &str, labels: &[Label]) -> Option<&dyn GaugeMetric> {
+        (**self).route_gauge(name, labels)
+    }
+    #[inline]
+    fn route_histogram(&self, name: &str, labels: &[Label]) -> Option<&dyn HistogramMetric> {
+        (**self).route_histogram(name, labels)
+    }
+    #[inline]
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        (**self).visit(visitor)
+    }
+    #[inline]
+    fn unknown_keys(&self) -> Option<&UnknownKeys> {
+        (**self).unknown_keys()
+    }
+    #[inline]
+    fn recorder(&self) -> Option<&dyn Recorder> {
+        (**self).recorder()
+    }
+}
+
+impl<R: Registry + ?Sized> Registry for std::sync::Arc<R> {
+    #[inline]
+    fn find_counter(&self, name: &str) -> Option<&dyn CounterMetric> {
+        (**self).find_counter(name)
+    }
+    #[inline]
+    fn find_gauge(&self, name: &str) -> Option<&dyn GaugeMetric> {
+        (**self).find_gauge(name)
+    }
+    #[inline]
+    fn find_histogram(&self, name: &str) -> Option<&dyn HistogramMetric> {
+        (**self).find_histogram(name)
+    }
+    #[inline]
+    fn find_description(&self, name: &str) -> Option<&'static str> {
+        (**self).find_description(name)
+    }
+    #[inline]
+    fn find_unit(&self, name: &str) -> Option<Unit> {
+        (**self).find_unit(name)
+    }
+    #[inline]
+    fn route_counter(&self, name: &str, labels: &[Label]) -> Option<&dyn CounterMetric> {
+        (**self).route_counter(name, labels)
+    }
+    #[inline]
+    fn route_gauge(&self, name: &str, labels: &[Label]) -> Option<&dyn GaugeMetric> {
+        (**self).route_gauge(name, labels)
+    }
+    #[inline]
+    fn route_histogram(&self, name: &str, labels: &[Label]) -> Option<&dyn HistogramMetric> {
+        (**self).route_histogram(name, labels)
+    }
+    #[inline]
+    fn visit(&self, visitor: &mut dyn MetricVisitor) {
+        (**self).visit(visitor)
+    }
+    #[inline]
+    fn unknown_keys(&self) -> Option<&UnknownKeys> {
+        (**self).unknown_keys()
+    }
+    #[inline]
+    fn recorder(&self) -> Option<&dyn Recorder> {
+        (**self).recorder()
+    }
+}
+
+/// Look-up of the metrics within a single scope, relative to that scope.
+/// The key separator is provided by the root, allowing a scope to be shared between catalogues.
+pub trait ScopedRegistry {
+    /// Find a registered counter under the provided relative name
+    fn find_scoped_counter(&self, name: &str, separator: &str) -> Option<&dyn CounterMetric> {
+        self.route_scoped_counter(name, &[], separator)
+    }
+    /// Find a registered gauge under the provided relative name
+    fn find_scoped_gauge(&self, name: &str, separator: &str) -> Option<&dyn GaugeMetric> {
+        self.route_scoped_gauge(name, &[], separator)
+    }
+    /// Find a registered [`Histogram`] under the provided relative name
+    fn find_scoped_histogram(&self, name: &str, separator: &str) -> Option<&dyn HistogramMetric> {
+        self.route_scoped_histogram(name, &[], separator)
+    }
+    /// Find the description of the metric registered under the provided relative name
+    fn find_scoped_description(&self, name: &str, separator: &str) -> Option<&'static str>;
+    /// Find the unit of the metric registered under the provided relative name
+    fn find_scoped_unit(&self, name: &str, separator: &str) -> Option<Unit>;
+    /// Find a registered counter under the provided relative name, or within the sub-scope routed by the labels
+    fn route_scoped_counter(
+        &self,
+        name: &str,
+        labels: &[Label],
+        separator: &str,
+    ) -> Option<&dyn CounterMetric>;
+    /// Find a registered gauge under the provided relative name, or within the sub-scope routed by the labels
+    fn route_scoped_gauge(
+        &self,
+        name: &str,
+        labels: &[Label],
+        separator: &str,
+    ) -> Option<&dyn GaugeMetric>;
+    /// Find a registered [`Histogram`] under the provided relative name, or within the sub-scope routed by the labels
+    fn route_scoped_histogram(
+        &self,
+        name: &str,
+        labels: &[Label],
+        separator: &str,
+    ) -> Option<&dyn HistogramMetric>;
+    /// Visit all registered metrics, prepending the provided prefix to their relative keys
+    fn visit_scoped(&self, prefix: &str, separator: &str, visitor: &mut dyn MetricVisitor);
+    /// Find a registered metric by its location, i.e. the positions of the fields leading to it,
+    /// along with its index within an array or family
+    fn locate_scoped(&self, location: &[usize]) -> Option<MetricRef<'_>>;
+}
+
+/// Reference to a registered metric, according to its kind
+#[derive(Clone, Copy)]
+pub enum MetricRef<'a> {
+    Counter(&'a dyn CounterMetric),
+    Gauge(&'a dyn GaugeMetric),
+    Histogram(&'a dyn HistogramMetric),
+}
+
+impl<'a> MetricRef<'a> {
+    /// Kind of the referenced metric
+    pub fn kind(&self) -> MetricKind {
+        match self {
+            MetricRef::Counter(_) => MetricKind::Counter,
+            MetricRef::Gauge(_) => MetricKind::Gauge,
+            MetricRef::Histogram(_) => MetricKind::Histogram,
+        }
+    }
+
+    /// The referenced metric, if it is a counter
+    pub fn as_counter(self) -> Option<&'a dyn CounterMetric> {
+        match self {
+            MetricRef::Counter(counter) => Some(counter),
+            _ => None,
+        }
+    }
+
+    /// The referenced metric, if it is a gauge
+    pub fn as_gauge(self) -> Option<&'a dyn GaugeMetric> {
+        match self {
+            MetricRef::Gauge(gauge) => Some(gauge),
+            _ => None,
+        }
+    }
+
+    /// The referenced metric, if it is a histogram
+    pub fn as_histogram(self) -> Option<&'a dyn HistogramMetric> {
+        match self {
+            MetricRef::Histogram(histogram) => Some(histogram),
+            _ => None,
+        }
+    }
+}
+
+/// Callback of [`Registry::visit`], called with the full key of each registered metric
+pub trait MetricVisitor {
+    fn visit(&mut self, key: &str, metric: MetricRef<'_>);
+}
+
+impl<F: FnMut(&str, MetricRef<'_>)> MetricVisitor for F {
+    fn visit(&mut self, key: &str, metric: MetricRef<'_>) {
+        self(key, metric)
+    }
+}
+
+/// Field of a scope at the provided position, projected by the generated accessors of a catalogue.
+/// Implemented along with the scope, its fields are accessed without naming their types or members,
+/// which may be private to the module of the scope.
+#[doc(hidden)]
+pub trait ScopeField<const POSITION: usize> {
+    type Field;
+
+    fn field(&self) -> &Self::Field;
+}
+
+/// Kind of the metric a key resolves to within a registry, if any
+#[doc(hidden)]
+pub fn resolve_kind<R: Registry + ?Sized>(registry: &R, key: &Key) -> Option<MetricKind> {
+    let (name, labels) = (key.name(), key.labels().as_slice());
+    if registry.route_counter(name, labels).is_some() {
+        Some(MetricKind::Counter)
+    } else if registry.route_gauge(name, labels).is_some() {
+        Some(MetricKind::Gauge)
+    } else if registry.route_histogram(name, labels).is_some() {
+        Some(MetricKind::Histogram)
+    } else {
+        None
+    }
+}
+
+/// Message of the assertion failed by the recorder of a strict root, for a key it can't resolve as the recorded kind
+#[doc(hidden)]
+pub fn unresolved_key<R: Registry + ?Sized>(registry: &R, key: &Key, kind: MetricKind) -> String {
+    match resolve_kind(registry, key) {
+        Some(resolved) => format!(
+            "Key `{}` of a {:?} recorded as a {:?}",
+            key.name(),
+            resolved,
+            kind
+        ),
+        None => format!("Unknown key `{}` recorded as a {:?}", key.name(), kind),
+    }
+}
+
+/// Index of an element within an array of metrics, from its relative `name{separator}index` key,
+/// or from the index label of the key otherwise
+#[doc(hidden)]
+pub fn metric_index(
+    key: &str,
+    name: &str,
+    labels: &[Label],
+    index_label: &str,
+    separator: &str,
+) -> Option<usize> {
+    let index = key.strip_prefix(name)?;
+    if index.is_empty() {
+        label_index(labels, index_label)
+    } else {
+        index.strip_prefix(separator)?.parse().ok()
+    }
+}
+
+/// Index of an element within an array of sub-scopes, along with the key relative to that element.
+/// The index is taken from the relative `name{separator}index{separator}key` key,
+/// or from the index label of the key otherwise
+#[doc(hidden)]
+pub fn scope_index<'a>(
+    key: &'a str,
+    name: &str,
+    labels: &[Label],
+    index_label: &str,
+    separator: &str,
+) -> Option<(usize, &'a str)> {
+    let key = key.strip_prefix(name)?.strip_prefix(separator)?;
+    if let Some((index, key)) = key.split_once(separator) {
+        if let Ok(index) = index.parse() {
+            return Some((index, key));
+        }
+    }
+    label_index(labels, index_label).map(|index| (index, key))
+}
+
+fn label_index(labels: &[Label], index_label: &str) -> Option<usize> {
+    labels
+        .iter()
+        .find(|label| label.key() == index_label)?
+        .value()
+        .parse()
+        .ok()
 }
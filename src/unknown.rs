@@ -0,0 +1,80 @@
+use crate::Counter;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+/// Samples dropped by the recorder of a root, counted per key unknown to its catalogue,
+/// e.g. a misspelled key passed to `metrics::increment_counter!`, when declared as a field of the root.
+/// At most [`UnknownKeys::CAPACITY`] keys are tracked, the misses of any further keys are counted under an empty key.
+pub struct UnknownKeys {
+    keys: RwLock<BTreeMap<String, Counter>>,
+    overflow: Counter,
+}
+
+impl Default for UnknownKeys {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl UnknownKeys {
+    /// Maximum number of distinct keys tracked
+    pub const CAPACITY: usize = 1024;
+
+    pub const fn new() -> Self {
+        Self {
+            keys: RwLock::new(BTreeMap::new()),
+            overflow: Counter::new(),
+        }
+    }
+
+    /// Count a sample dropped for the provided key
+    #[doc(hidden)]
+    pub fn record(&self, key: &str) {
+        {
+            let keys = self.keys.read().unwrap_or_else(|err| err.into_inner());
+            if let Some(misses) = keys.get(key) {
+                misses.increment(1);
+                return;
+            }
+        }
+        let mut keys = self.keys.write().unwrap_or_else(|err| err.into_inner());
+        if keys.len() < Self::CAPACITY || keys.contains_key(key) {
+            keys.entry(key.to_string()).or_default().increment(1);
+        } else {
+            self.overflow.increment(1);
+        }
+    }
+
+    /// Number of samples dropped for the provided key
+    pub fn get(&self, key: &str) -> u64 {
+        let keys = self.keys.read().unwrap_or_else(|err| err.into_inner());
+        if key.is_empty() {
+            return self.overflow.read();
+        }
+        keys.get(key).map_or(0, Counter::read)
+    }
+
+    /// Number of samples dropped for all keys
+    pub fn total(&self) -> u64 {
+        let keys = self.keys.read().unwrap_or_else(|err| err.into_inner());
+        keys.values().map(Counter::read).sum::<u64>() + self.overflow.read()
+    }
+
+    /// Whether no sample was dropped
+    pub fn is_empty(&self) -> bool {
+        self.total() == 0
+    }
+
+    /// Calls `f` with each key & its number of dropped samples, ordered by key,
+    /// followed by the samples dropped for the keys beyond the capacity, if any
+    pub fn for_each(&self, mut f: impl FnMut(&str, u64)) {
+        let keys = self.keys.read().unwrap_or_else(|err| err.into_inner());
+        for (key, misses) in keys.iter() {
+            f(key, misses.read());
+        }
+        let overflow = self.overflow.read();
+        if overflow > 0 {
+            f("", overflow);
+        }
+    }
+}
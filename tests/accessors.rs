@@ -0,0 +1,97 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Server {
+    requests: Counter,
+    #[metric("type")]
+    kind: Gauge,
+    latency: Histogram<60>,
+    responses: CounterFamily<Status>,
+    connections: [Gauge; 2],
+    workers: [Worker; 3],
+    queue: Queue,
+    storage: storage::Storage,
+}
+
+#[derive(Catalogue)]
+struct Worker {
+    busy: DiscreteGauge,
+    shards: [Shard; 2],
+}
+
+#[derive(Catalogue)]
+struct Shard {
+    jobs: Counter,
+}
+
+#[derive(Catalogue)]
+struct Queue(Shard);
+
+#[derive(FamilyKey)]
+enum Status {
+    Ok,
+    NotFound,
+}
+
+mod storage {
+    use metrics_catalogue::*;
+
+    #[derive(Catalogue)]
+    pub struct Storage {
+        writes: Counter,
+    }
+
+    impl Storage {
+        pub fn writes(&self) -> u64 {
+            self.writes.read()
+        }
+    }
+}
+
+static CATALOGUE: Server = Server::new();
+
+#[test]
+fn access_metrics_by_key() {
+    let requests: &'static Counter = server::requests(&CATALOGUE);
+    for _ in 0..3 {
+        requests.increment(1);
+    }
+    assert_eq!(CATALOGUE.requests.read(), 3);
+
+    server::r#type(&CATALOGUE).set(2.0);
+    assert_eq!(CATALOGUE.kind.read(), 2.0);
+    server::latency(&CATALOGUE).insert(1.0);
+    assert_eq!(CATALOGUE.latency.read(), vec![1.0]);
+    server::responses::not_found(&CATALOGUE).increment(2);
+    assert_eq!(CATALOGUE.responses.get(Status::NotFound).read(), 2);
+    assert_eq!(CATALOGUE.responses.get(Status::Ok).read(), 0);
+    server::connections(&CATALOGUE, 1).increase(4.0);
+    assert_eq!(CATALOGUE.connections[1].read(), 4.0);
+}
+
+#[test]
+fn access_nested_metrics() {
+    let catalogue = Server::new();
+    server::workers::busy(&catalogue, 2).set(1.0);
+    assert_eq!(catalogue.workers[2].busy.read(), 1.0);
+    server::workers::shards::jobs(&catalogue, 1, 0).increment(5);
+    assert_eq!(catalogue.workers[1].shards[0].jobs.read(), 5);
+    assert_eq!(catalogue.workers[0].shards[1].jobs.read(), 0);
+    server::queue::jobs(&catalogue).increment(1);
+    assert_eq!(catalogue.queue.0.jobs.read(), 1);
+    server::storage::writes(&catalogue).increment(6);
+    assert_eq!(catalogue.storage.writes(), 6);
+
+    // Accessors resolve to the same metrics as their keys
+    let jobs = catalogue
+        .find_counter(server::workers::shards::JOBS[1][0])
+        .unwrap();
+    assert_eq!(jobs.read(), 5);
+}
+
+#[test]
+#[should_panic]
+fn access_out_of_bounds() {
+    server::workers::busy(&CATALOGUE, 3);
+}
@@ -0,0 +1,82 @@
+use metrics::{gauge, increment_counter};
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Cores {
+    /// Requests handled by each core
+    #[metric(index_label = "core")]
+    per_core: [Counter; 4],
+    #[metric(index_label = "worker")]
+    workers: [Worker; 2],
+    total: Counter,
+}
+
+#[derive(Catalogue)]
+struct Worker {
+    #[metric(unit = "seconds")]
+    busy: Gauge,
+    latency: [Histogram<60>; 2],
+}
+
+static CATALOGUE: Cores = Cores::new();
+
+#[test]
+fn array_keys() {
+    assert_eq!(cores::PER_CORE[3], "cores.per_core.3");
+    assert_eq!(cores::workers::BUSY[1], "cores.workers.1.busy");
+    assert_eq!(cores::workers::LATENCY[1][0], "cores.workers.1.latency.0");
+
+    let t = Cores::new();
+    assert!(t.find_counter(cores::PER_CORE[3]).is_some());
+    assert!(t.find_counter("cores.per_core.4").is_none());
+    assert!(t.find_gauge(cores::workers::BUSY[1]).is_some());
+    assert!(t.find_histogram(cores::workers::LATENCY[1][1]).is_some());
+    assert_eq!(
+        t.find_description(cores::PER_CORE[0]),
+        Some("Requests handled by each core")
+    );
+    assert_eq!(t.find_unit(cores::workers::BUSY[0]), Some(Unit::Seconds));
+}
+
+#[test]
+fn array_recording() {
+    metrics::set_recorder(&CATALOGUE).unwrap();
+
+    increment_counter!(cores::PER_CORE[1]);
+    increment_counter!("cores.per_core", "core" => "2");
+    increment_counter!("cores.per_core", "core" => "7");
+    gauge!("cores.workers.busy", 3.0, "worker" => "1");
+    gauge!(cores::workers::BUSY[0], 2.0);
+
+    assert_eq!(CATALOGUE.per_core[1].read(), 1);
+    assert_eq!(CATALOGUE.per_core[2].read(), 1);
+    assert_eq!(CATALOGUE.workers[0].busy.read(), 2.0);
+    assert_eq!(CATALOGUE.workers[1].busy.read(), 3.0);
+
+    #[cfg(feature = "prometheus")]
+    {
+        use metrics_catalogue::prometheus::StringRender;
+
+        let mut s = String::new();
+        CATALOGUE.render("", "", &mut s);
+        assert!(s.contains(concat!(
+            "# HELP cores_per_core Requests handled by each core\n",
+            "# TYPE cores_per_core counter\n",
+            "cores_per_core{core=\"0\"} 0\n",
+            "cores_per_core{core=\"1\"} 1\n",
+            "cores_per_core{core=\"2\"} 1\n",
+            "cores_per_core{core=\"3\"} 0\n",
+            "\n",
+        )));
+        assert!(s.contains(concat!(
+            "# UNIT cores_workers_busy_seconds seconds\n",
+            "# TYPE cores_workers_busy_seconds gauge\n",
+            "cores_workers_busy_seconds{worker=\"0\"} 2\n",
+            "cores_workers_busy_seconds{worker=\"1\"} 3\n",
+            "\n",
+        )));
+        assert!(s.contains("cores_workers_latency_count{worker=\"1\",index=\"0\"} 0\n"));
+        assert_eq!(s.matches("# TYPE cores_workers_latency ").count(), 1);
+    }
+}
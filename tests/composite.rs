@@ -0,0 +1,201 @@
+#[cfg(feature = "prometheus")]
+use metrics_catalogue::prometheus::StringRender;
+use metrics_catalogue::*;
+use std::sync::Arc;
+
+#[derive(Catalogue)]
+#[metric(root, "")]
+struct App {
+    requests: Counter,
+    connections: [Gauge; 2],
+    misses: UnknownKeys,
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Server {
+    latency: Histogram<60>,
+    misses: UnknownKeys,
+}
+
+#[derive(Catalogue)]
+struct Cache {
+    hits: Counter,
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Proxy {
+    requests: Counter,
+    unknown: DynamicRegistry<10>,
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Plain {
+    requests: Counter,
+}
+
+#[derive(Catalogue)]
+#[metric(root, strict)]
+struct Guard {
+    checks: Counter,
+}
+
+static SERVER: Server = Server::new();
+
+fn composite(app: &Arc<App>) -> CompositeRegistry {
+    CompositeRegistry::new()
+        .mount("app", app.clone())
+        .mount("", metrics_catalogue_external::Metrics::new())
+        .mount("web", &SERVER)
+        .mount("cache", Cache::new())
+}
+
+#[test]
+fn mounted_keys() {
+    let app = Arc::new(App::new());
+    let composite = composite(&app);
+    composite.increment_counter(&Key::from_name("app.requests"), 1);
+    composite.update_gauge(
+        &Key::from_name("app.connections.1"),
+        GaugeValue::Absolute(2.0),
+    );
+    composite.increment_counter(&Key::from_name(metrics_catalogue_external::db::QUERIES), 3);
+    composite.record_histogram(&Key::from_name("web.server.latency"), 1.0);
+    composite.increment_counter(&Key::from_name("cache.hits"), 4);
+    assert_eq!(app.requests.read(), 1);
+    assert_eq!(app.connections[1].read(), 2.0);
+    assert_eq!(SERVER.latency.read(), vec![1.0]);
+    assert_eq!(composite.find_counter("db.queries").unwrap().read(), 3);
+    assert_eq!(composite.find_counter("cache.hits").unwrap().read(), 4);
+    assert!(composite.unknown_keys().unwrap().is_empty());
+
+    // Keys outside of their mount prefix are unknown
+    composite.increment_counter(&Key::from_name("requests"), 1);
+    composite.record_histogram(&Key::from_name("server.latency"), 1.0);
+    composite.increment_counter(&Key::from_name("appl.requests"), 1);
+    let unknown_keys = composite.unknown_keys().unwrap();
+    assert_eq!(unknown_keys.total(), 3);
+    assert_eq!(unknown_keys.get("server.latency"), 1);
+    assert_eq!(app.requests.read(), 1);
+}
+
+#[test]
+fn forward_unknown_keys_to_mounted_roots() {
+    let (app, proxy) = (Arc::new(App::new()), Arc::new(Proxy::new()));
+    let composite = composite(&app).mount("edge", proxy.clone());
+    let hits = Key::from_parts("edge.proxy.hits", vec![Label::new("route", "/")]);
+    composite.register_counter(&hits, None, Some("Hits"));
+    composite.increment_counter(&hits, 2);
+    composite.increment_counter(&Key::from_name("edge.proxy.requests"), 1);
+    composite.increment_counter(&Key::from_name("app.orders"), 1);
+    assert_eq!(proxy.requests.read(), 1);
+    assert_eq!(proxy.unknown.len(), 1);
+    assert_eq!(app.unknown_keys().unwrap().get("orders"), 1);
+    assert!(composite.unknown_keys().unwrap().is_empty());
+    #[cfg(feature = "prometheus")]
+    {
+        let mut output = String::new();
+        composite.render("", "", &mut output);
+        assert!(
+            output.contains("edge_proxy_hits{route=\"/\"} 2\n"),
+            "{}",
+            output
+        );
+    }
+
+    // Keys matching no prefix are counted by the composite
+    composite.increment_counter(&Key::from_name("proxy.hits"), 1);
+    assert_eq!(composite.unknown_keys().unwrap().get("proxy.hits"), 1);
+    assert_eq!(proxy.unknown.len(), 1);
+}
+
+#[test]
+fn count_misses_of_roots_dropping_them() {
+    let composite = CompositeRegistry::new().mount("app", Plain::new());
+    composite.increment_counter(&Key::from_name("app.plain.requests"), 1);
+    composite.increment_counter(&Key::from_name("app.plain.typo"), 1);
+    let unknown_keys = composite.unknown_keys().unwrap();
+    assert_eq!(unknown_keys.get("app.plain.typo"), 1);
+    assert_eq!(unknown_keys.total(), 1);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Unknown key `guard.check` recorded as a Counter")]
+fn forward_unknown_keys_to_strict_root() {
+    let composite = CompositeRegistry::new().mount("auth", Guard::new());
+    composite.increment_counter(&Key::from_name("auth.guard.checks"), 1);
+    composite.increment_counter(&Key::from_name("auth.guard.check"), 1);
+}
+
+#[test]
+fn visit_mounted_keys() {
+    let composite = composite(&Arc::new(App::new()));
+    let mut keys = Vec::new();
+    composite.visit(&mut |key: &str, _metric: MetricRef<'_>| keys.push(key.to_string()));
+    assert_eq!(
+        keys,
+        [
+            "app.requests",
+            "app.connections.0",
+            "app.connections.1",
+            "db.queries",
+            "db.pool.connections",
+            "web.server.latency",
+            "cache.hits",
+        ]
+    );
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_mounted_keys() {
+    let composite = composite(&Arc::new(App::new()));
+    composite.increment_counter(&Key::from_name("cache.miss"), 1);
+    let mut output = String::new();
+    composite.render("", "", &mut output);
+    let lines = output.lines().collect::<Vec<_>>();
+    for line in [
+        "# TYPE app_requests counter",
+        "app_connections{index=\"1\"} 0",
+        "# TYPE db_queries counter",
+        "# TYPE db_pool_connections gauge",
+        "# TYPE web_server_latency histogram",
+        "# TYPE cache_hits counter",
+        "catalogue_unknown_key_total{key=\"cache.miss\"} 1",
+    ] {
+        assert!(lines.contains(&line), "No {} in output:\n{}", line, output);
+    }
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_merged_unknown_keys() {
+    let app = Arc::new(App::new());
+    let composite = composite(&app);
+    composite.increment_counter(&Key::from_name("cache.miss"), 1);
+    // Misses recorded by the mounted roots themselves
+    app.increment_counter(&Key::from_name("orders"), 2);
+    SERVER.increment_counter(&Key::from_name("server.errors"), 1);
+    let mut output = String::new();
+    composite.render("", "", &mut output);
+    let lines = output.lines().collect::<Vec<_>>();
+    for line in [
+        "catalogue_unknown_key_total{key=\"cache.miss\"} 1",
+        "catalogue_unknown_key_total{key=\"app.orders\"} 1",
+        "catalogue_unknown_key_total{key=\"web.server.errors\"} 1",
+    ] {
+        assert!(lines.contains(&line), "No {} in output:\n{}", line, output);
+    }
+    let headers = lines
+        .iter()
+        .filter(|line| line.starts_with("# TYPE catalogue_unknown_key_total"))
+        .count();
+    assert_eq!(
+        headers, 1,
+        "Several unknown key families in output:\n{}",
+        output
+    );
+}
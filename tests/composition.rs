@@ -0,0 +1,89 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+pub struct Service {
+    requests: Counter,
+    cache: Cache,
+    storage: storage::Storage,
+}
+
+use cache::Cache;
+
+mod cache {
+    use metrics_catalogue::*;
+
+    #[derive(Catalogue)]
+    pub struct Cache {
+        pub hits: Counter,
+    }
+}
+
+mod storage {
+    use metrics_catalogue::*;
+
+    #[derive(Catalogue)]
+    pub struct Storage {
+        pub writes: Counter,
+        pub shard: Shard,
+    }
+
+    #[derive(Catalogue)]
+    pub struct Shard {
+        pub size: Gauge,
+    }
+}
+
+#[test]
+fn sub_scopes_across_modules() {
+    assert_eq!(service::REQUESTS, "service.requests");
+    assert_eq!(service::cache::HITS, "service.cache.hits");
+    assert_eq!(service::storage::WRITES, "service.storage.writes");
+    assert_eq!(service::storage::shard::SIZE, "service.storage.shard.size");
+
+    let t = Service::new();
+    t.increment_counter(&Key::from_name(service::cache::HITS), 1);
+    t.increment_counter(&Key::from_name(service::storage::WRITES), 2);
+    t.update_gauge(
+        &Key::from_name(service::storage::shard::SIZE),
+        GaugeValue::Absolute(3.0),
+    );
+    assert_eq!(t.cache.hits.read(), 1);
+    assert_eq!(t.storage.writes.read(), 2);
+    assert_eq!(t.storage.shard.size.read(), 3.0);
+}
+
+/// Public scopes sharing their name & fields across modules
+pub mod a {
+    use metrics_catalogue::*;
+
+    #[derive(Catalogue)]
+    pub struct Metrics {
+        pub requests: Counter,
+    }
+}
+
+pub mod b {
+    use metrics_catalogue::*;
+
+    #[derive(Catalogue)]
+    pub struct Metrics {
+        pub requests: Counter,
+    }
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Proxy {
+    a: a::Metrics,
+    b: b::Metrics,
+}
+
+#[test]
+fn identical_public_scopes() {
+    let t = Proxy::new();
+    t.increment_counter(&Key::from_name(proxy::b::REQUESTS), 1);
+    assert_eq!(proxy::a::REQUESTS, "proxy.a.requests");
+    assert_eq!(t.a.requests.read(), 0);
+    assert_eq!(t.b.requests.read(), 1);
+}
@@ -0,0 +1,58 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Described {
+    /// Number of handled requests
+    requests: Counter,
+    /// Ignored in favour of the explicit description
+    #[metric(description = "Number of open connections")]
+    connections: Gauge,
+    /// Request latency,
+    /// spanning multiple lines
+    latency: Histogram<60>,
+    undescribed: Counter,
+    sub: DescribedSub,
+}
+
+#[derive(Catalogue)]
+struct DescribedSub {
+    /// Nested counter
+    nested: Counter,
+}
+
+#[test]
+fn registry_descriptions() {
+    let t = Described::new();
+    let known_descriptions = [
+        (described::REQUESTS, Some("Number of handled requests")),
+        (described::CONNECTIONS, Some("Number of open connections")),
+        (
+            described::LATENCY,
+            Some("Request latency, spanning multiple lines"),
+        ),
+        (described::UNDESCRIBED, None),
+        (described::sub::NESTED, Some("Nested counter")),
+        ("described.unknown", None),
+    ];
+    for (key, description) in known_descriptions {
+        assert_eq!(t.find_description(key), description, "key {}", key);
+    }
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn help_lines() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let t = Described::new();
+    let mut s = String::new();
+    t.render("", "", &mut s);
+    assert!(s.contains(
+        "# HELP described_requests Number of handled requests\n# TYPE described_requests counter\n"
+    ));
+    assert!(s.contains("# HELP described_connections Number of open connections\n"));
+    assert!(s.contains("# HELP described_latency Request latency, spanning multiple lines\n"));
+    assert!(s.contains("# HELP described_sub_nested Nested counter\n"));
+    assert!(!s.contains("# HELP described.undescribed"));
+}
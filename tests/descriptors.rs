@@ -0,0 +1,95 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Server {
+    /// Number of handled requests
+    #[metric(label(method = "get"))]
+    requests: Counter,
+    #[metric(unit = "seconds")]
+    latency: Histogram<60>,
+    #[metric(skip)]
+    _hidden: Gauge,
+    responses: CounterFamily<Status>,
+    workers: [Worker; 2],
+    #[metric(instance_label = "shard")]
+    primary: Shard,
+}
+
+#[derive(Catalogue)]
+struct Worker {
+    busy: DiscreteGauge,
+}
+
+#[derive(Catalogue)]
+struct Shard {
+    size: Gauge,
+}
+
+#[derive(FamilyKey)]
+enum Status {
+    Ok,
+    NotFound,
+}
+
+#[test]
+fn metric_descriptors() {
+    let keys = server::METRICS
+        .iter()
+        .map(|descriptor| descriptor.key)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        keys,
+        [
+            server::REQUESTS,
+            server::LATENCY,
+            server::responses::OK,
+            server::responses::NOT_FOUND,
+            server::primary::SIZE,
+            server::workers::BUSY[0],
+            server::workers::BUSY[1],
+        ]
+    );
+
+    assert_eq!(
+        server::METRICS[0],
+        MetricDescriptor {
+            key: "server.requests",
+            kind: MetricKind::Counter,
+            unit: None,
+            description: Some("Number of handled requests"),
+            labels: &[("method", "get")],
+            path: "requests",
+        }
+    );
+    assert_eq!(server::METRICS[1].kind, MetricKind::Histogram);
+    assert_eq!(server::METRICS[1].unit, Some(Unit::Seconds));
+
+    let not_found = &server::METRICS[3];
+    assert_eq!(not_found.path, "responses[Status::NotFound]");
+    assert_eq!(not_found.labels, &[("status", "not_found")]);
+
+    let shard = &server::METRICS[4];
+    assert_eq!(shard.kind, MetricKind::Gauge);
+    assert_eq!(shard.path, "primary.size");
+    assert_eq!(shard.labels, &[("shard", "primary")]);
+
+    let worker = &server::METRICS[6];
+    assert_eq!(worker.path, "workers[1].busy");
+    assert_eq!(worker.labels, &[("index", "1")]);
+}
+
+#[test]
+fn descriptors_resolve_in_registry() {
+    let catalogue = Server::new();
+    catalogue.responses.get(Status::NotFound).increment(1);
+    assert_eq!(catalogue.responses.get(Status::Ok).read(), 0);
+    for descriptor in server::METRICS {
+        let found = match descriptor.kind {
+            MetricKind::Counter => catalogue.find_counter(descriptor.key).is_some(),
+            MetricKind::Gauge => catalogue.find_gauge(descriptor.key).is_some(),
+            MetricKind::Histogram => catalogue.find_histogram(descriptor.key).is_some(),
+        };
+        assert!(found, "{}", descriptor.key);
+    }
+}
@@ -0,0 +1,110 @@
+#[cfg(feature = "prometheus")]
+use metrics_catalogue::prometheus::StringRender;
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Server {
+    requests: Counter,
+    connections: [Gauge; 2],
+    unknown: DynamicRegistry<3>,
+}
+
+#[test]
+fn record_unknown_keys() {
+    let catalogue = Server::new();
+    catalogue.increment_counter(&Key::from_name(server::REQUESTS), 1);
+    catalogue.increment_counter(&Key::from_name("http.requests"), 2);
+    catalogue.update_gauge(
+        &Key::from_name("http.connections"),
+        GaugeValue::Absolute(4.0),
+    );
+    assert_eq!(catalogue.requests.read(), 1);
+    assert_eq!(catalogue.unknown.len(), 2);
+
+    // Keys of the catalogue are never created in the dynamic registry
+    catalogue.register_gauge(&Key::from_name(server::CONNECTIONS[1]), None, None);
+    catalogue.update_gauge(
+        &Key::from_name(server::CONNECTIONS[1]),
+        GaugeValue::Increment(1.0),
+    );
+    assert_eq!(catalogue.connections[1].read(), 1.0);
+    assert_eq!(catalogue.unknown.len(), 2);
+
+    // Keys sharing the name of another kind are dropped
+    catalogue.update_gauge(&Key::from_name("http.requests"), GaugeValue::Increment(1.0));
+    assert_eq!(catalogue.unknown.len(), 2);
+}
+
+#[test]
+fn cap_unknown_keys() {
+    let catalogue = Server::new();
+    for status in ["200", "404", "500", "503"] {
+        let labels = vec![Label::new("status", status)];
+        catalogue.increment_counter(&Key::from_parts("http.responses", labels), 1);
+    }
+    assert_eq!(catalogue.unknown.len(), 3);
+    catalogue.register_histogram(&Key::from_name("http.latency"), None, None);
+    assert_eq!(catalogue.unknown.len(), 3);
+
+    // Existing keys are still recorded once the registry is full
+    let labels = vec![Label::new("status", "200")];
+    catalogue.increment_counter(&Key::from_parts("http.responses", labels), 1);
+    assert_eq!(catalogue.unknown.len(), 3);
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_unknown_keys() {
+    let catalogue = Server::new();
+    catalogue.register_counter(
+        &Key::from_name("http.requests"),
+        Some(Unit::Count),
+        Some("Requests received over HTTP"),
+    );
+    catalogue.increment_counter(
+        &Key::from_parts("http.requests", vec![Label::new("method", "GET")]),
+        2,
+    );
+    catalogue.record_histogram(&Key::from_name("http.latency"), 1.0);
+
+    let mut output = String::new();
+    catalogue.render("", "", &mut output);
+    let lines = output.lines().collect::<Vec<_>>();
+    for line in [
+        "# TYPE server_requests counter",
+        "# HELP http_requests_count Requests received over HTTP",
+        "# UNIT http_requests_count count",
+        "# TYPE http_requests_count counter",
+        "http_requests_count 0",
+        "http_requests_count{method=\"GET\"} 2",
+        "# TYPE http_latency histogram",
+    ] {
+        assert!(lines.contains(&line), "No {} in output", line);
+    }
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn drop_keys_of_the_catalogue_recorded_as_another_kind() {
+    let catalogue = Server::new();
+    catalogue.increment_counter(&Key::from_name(server::REQUESTS), 1);
+    catalogue.register_gauge(&Key::from_name(server::REQUESTS), None, None);
+    catalogue.update_gauge(&Key::from_name(server::REQUESTS), GaugeValue::Absolute(3.0));
+    catalogue.record_histogram(&Key::from_name(server::CONNECTIONS[0]), 1.0);
+    assert!(catalogue.unknown.is_empty());
+
+    let mut output = String::new();
+    catalogue.render("", "", &mut output);
+    let families = output
+        .lines()
+        .filter(|line| line.starts_with("# TYPE server_requests "))
+        .count();
+    assert_eq!(
+        families, 1,
+        "Several server.requests families in output:\n{}",
+        output
+    );
+    assert!(output.contains("server_requests 1\n"), "{}", output);
+    assert!(!output.contains("server.connections_sum"), "{}", output);
+}
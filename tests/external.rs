@@ -0,0 +1,42 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct App {
+    requests: Counter,
+    db: metrics_catalogue_external::Metrics,
+}
+
+#[test]
+fn external_keys() {
+    assert_eq!(app::REQUESTS, "app.requests");
+    assert_eq!(app::db::QUERIES, "app.db.queries");
+    assert_eq!(app::db::pool::CONNECTIONS, "app.db.pool.connections");
+
+    // The external catalogue remains usable on its own
+    assert_eq!(metrics_catalogue_external::db::QUERIES, "db.queries");
+}
+
+#[test]
+fn external_registry() {
+    let t = App::new();
+    t.increment_counter(&Key::from_name(app::db::QUERIES), 1);
+    t.update_gauge(
+        &Key::from_name(app::db::pool::CONNECTIONS),
+        GaugeValue::Increment(2.0),
+    );
+    assert_eq!(t.db.queries.read(), 1);
+    assert_eq!(t.db.pool.connections.read(), 2.0);
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn external_render() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let t = App::new();
+    let mut s = String::new();
+    t.render("", "", &mut s);
+    assert!(s.contains("# TYPE app_db_queries counter\n"));
+    assert!(s.contains("# TYPE app_db_pool_connections gauge\n"));
+}
@@ -0,0 +1,12 @@
+use metrics_catalogue::*;
+
+mod pool;
+
+pub use pool::Pool;
+
+#[derive(Catalogue)]
+#[metric(root, "db")]
+pub struct Metrics {
+    pub queries: Counter,
+    pub pool: Pool,
+}
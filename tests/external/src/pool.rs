@@ -0,0 +1,6 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+pub struct Pool {
+    pub connections: Gauge,
+}
@@ -0,0 +1,95 @@
+use metrics_catalogue::*;
+use std::sync::Mutex;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Server {
+    requests: Counter,
+    connections: Gauge,
+    latency: Histogram<60>,
+    #[metric(route(region = "eu"))]
+    eu: Region,
+}
+
+#[derive(Catalogue)]
+struct Region {
+    hits: Counter,
+}
+
+/// Recorder keeping track of the samples it receives
+#[derive(Default)]
+struct Samples(Mutex<Vec<String>>);
+
+impl Samples {
+    fn push(&self, sample: String) {
+        self.0.lock().unwrap().push(sample);
+    }
+
+    fn take(&self) -> Vec<String> {
+        std::mem::take(&mut self.0.lock().unwrap())
+    }
+}
+
+impl Recorder for Samples {
+    fn register_counter(&self, key: &Key, _unit: Option<Unit>, _desc: Option<&'static str>) {
+        self.push(format!("register {}", key.name()));
+    }
+
+    fn register_gauge(&self, key: &Key, _unit: Option<Unit>, _desc: Option<&'static str>) {
+        self.push(format!("register {}", key.name()));
+    }
+
+    fn register_histogram(&self, key: &Key, _unit: Option<Unit>, _desc: Option<&'static str>) {
+        self.push(format!("register {}", key.name()));
+    }
+
+    fn increment_counter(&self, key: &Key, value: u64) {
+        self.push(format!("{} +{}", key.name(), value));
+    }
+
+    fn update_gauge(&self, key: &Key, _value: GaugeValue) {
+        self.push(format!("{} =", key.name()));
+    }
+
+    fn record_histogram(&self, key: &Key, value: f64) {
+        self.push(format!("{} {}", key.name(), value));
+    }
+}
+
+#[test]
+fn forward_unknown_keys() {
+    let catalogue = Server::new();
+    let recorder = FallbackRecorder::new(&catalogue, Samples::default());
+
+    recorder.register_counter(&Key::from_name(server::REQUESTS), None, None);
+    recorder.increment_counter(&Key::from_name(server::REQUESTS), 2);
+    recorder.update_gauge(
+        &Key::from_name(server::CONNECTIONS),
+        GaugeValue::Absolute(3.0),
+    );
+    recorder.record_histogram(&Key::from_name(server::LATENCY), 1.0);
+    let routed = Key::from_parts("server.hits", vec![Label::new("region", "eu")]);
+    recorder.increment_counter(&routed, 1);
+    assert_eq!(catalogue.requests.read(), 2);
+    assert_eq!(catalogue.connections.read(), 3.0);
+    assert_eq!(catalogue.latency.read(), vec![1.0]);
+    assert_eq!(catalogue.eu.hits.read(), 1);
+    assert!(recorder.fallback().take().is_empty());
+
+    recorder.register_gauge(&Key::from_name("http.connections"), None, None);
+    recorder.increment_counter(&Key::from_name("http.requests"), 1);
+    // Known keys recorded as another kind are unknown to the catalogue
+    recorder.record_histogram(&Key::from_name(server::REQUESTS), 2.0);
+    let unrouted = Key::from_parts("server.hits", vec![Label::new("region", "us")]);
+    recorder.increment_counter(&unrouted, 1);
+    assert_eq!(
+        recorder.fallback().take(),
+        [
+            "register http.connections",
+            "http.requests +1",
+            "server.requests 2",
+            "server.hits +1",
+        ]
+    );
+    assert_eq!(catalogue.eu.hits.read(), 1);
+}
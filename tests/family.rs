@@ -0,0 +1,99 @@
+use metrics::increment_counter;
+use metrics_catalogue::*;
+
+#[derive(FamilyKey)]
+enum Status {
+    Ok,
+    NotFound,
+    #[metric("internal_error")]
+    Error,
+}
+
+mod keys {
+    use metrics_catalogue::*;
+
+    #[derive(FamilyKey)]
+    #[metric("op")]
+    pub enum Operation {
+        Read,
+        Write,
+    }
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Server {
+    /// Responses by status
+    responses: CounterFamily<Status>,
+    #[metric(unit = "seconds")]
+    latency: HistogramFamily<keys::Operation, 60>,
+    shards: [Shard; 2],
+}
+
+#[derive(Catalogue)]
+struct Shard {
+    operations: GaugeFamily<keys::Operation>,
+}
+
+static CATALOGUE: Server = Server::new();
+
+#[test]
+fn family_keys() {
+    assert_eq!(server::RESPONSES, "server.responses");
+    assert_eq!(server::responses::NOT_FOUND, "server.responses.not_found");
+    assert_eq!(server::responses::ERROR, "server.responses.internal_error");
+    assert_eq!(server::latency::WRITE, "server.latency.write");
+    assert_eq!(
+        server::shards::operations::READ[1],
+        "server.shards.1.operations.read"
+    );
+
+    let t = Server::new();
+    assert!(t.find_counter(server::responses::OK).is_some());
+    assert!(t.find_counter("server.responses.unknown").is_none());
+    assert!(t.find_gauge(server::shards::operations::WRITE[0]).is_some());
+    assert_eq!(
+        t.find_description(server::responses::OK),
+        Some("Responses by status")
+    );
+    assert_eq!(t.find_unit(server::LATENCY), Some(Unit::Seconds));
+    assert_eq!(t.find_unit(server::latency::READ), Some(Unit::Seconds));
+}
+
+#[test]
+fn family_recording() {
+    metrics::set_recorder(&CATALOGUE).unwrap();
+
+    CATALOGUE.responses.get(Status::Ok).increment(1);
+    CATALOGUE.latency.get(keys::Operation::Write).insert(1.0);
+    CATALOGUE.shards[1]
+        .operations
+        .get(keys::Operation::Read)
+        .set(2.0);
+    increment_counter!(server::responses::NOT_FOUND);
+    increment_counter!("server.responses", "status" => "not_found");
+    increment_counter!("server.responses", "status" => "internal_error");
+    increment_counter!("server.responses", "status" => "unknown");
+
+    assert_eq!(CATALOGUE.responses.get(Status::Ok).read(), 1);
+    assert_eq!(CATALOGUE.responses.get(Status::NotFound).read(), 2);
+    assert_eq!(CATALOGUE.responses.get(Status::Error).read(), 1);
+
+    #[cfg(feature = "prometheus")]
+    {
+        use metrics_catalogue::prometheus::StringRender;
+
+        let mut s = String::new();
+        CATALOGUE.render("", "", &mut s);
+        assert!(s.contains(concat!(
+            "# HELP server_responses Responses by status\n",
+            "# TYPE server_responses counter\n",
+            "server_responses{status=\"ok\"} 1\n",
+            "server_responses{status=\"not_found\"} 2\n",
+            "server_responses{status=\"internal_error\"} 1\n",
+            "\n",
+        )));
+        assert!(s.contains("server_latency_seconds_count{op=\"write\"} 1\n"));
+        assert!(s.contains("server_shards_operations{index=\"1\",op=\"read\"} 2\n"));
+    }
+}
@@ -0,0 +1,96 @@
+use metrics_catalogue::*;
+use std::marker::PhantomData;
+
+trait Connection {
+    const KIND: &'static str;
+}
+
+struct Tcp;
+
+impl Connection for Tcp {
+    const KIND: &'static str = "tcp";
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Pools<T: Connection>
+where
+    T: 'static,
+{
+    primary: PoolMetrics<T>,
+    replicas: [PoolMetrics<T>; 2],
+    connections: Gauge,
+}
+
+#[derive(Catalogue)]
+struct PoolMetrics<T> {
+    acquired: Counter,
+    #[metric(skip)]
+    _connection: PhantomData<T>,
+}
+
+struct ConnectionPool<T: Connection> {
+    metrics: PoolMetrics<T>,
+}
+
+static CATALOGUE: Pools<Tcp> = Pools::new();
+
+#[test]
+fn generic_catalogue() {
+    let pool = ConnectionPool::<Tcp> {
+        metrics: PoolMetrics::new(),
+    };
+    pool.metrics.acquired.increment(1);
+    assert_eq!(pool.metrics.acquired.read(), 1);
+    assert_eq!(Tcp::KIND, "tcp");
+
+    CATALOGUE.replicas[1].acquired.increment(2);
+    assert_eq!(pools::primary::ACQUIRED, "pools.primary.acquired");
+    assert_eq!(
+        CATALOGUE
+            .find_counter(pools::replicas::ACQUIRED[1])
+            .unwrap()
+            .read(),
+        2
+    );
+    assert!(CATALOGUE.find_gauge(pools::CONNECTIONS).is_some());
+    assert_eq!(pools::replicas::acquired(&CATALOGUE, 1).read(), 2);
+}
+
+struct Udp;
+
+impl Connection for Udp {
+    const KIND: &'static str = "udp";
+}
+
+#[test]
+fn generic_accessors() {
+    let tcp = Pools::<Tcp>::new();
+    let udp = Pools::<Udp>::new();
+
+    // Accessors project the fields of any instance of the generic root, e.g. of its `PoolMetrics<T>`
+    let acquired: &Counter = pools::primary::acquired(&tcp);
+    acquired.increment(1);
+    pools::primary::acquired(&udp).increment(2);
+    pools::replicas::acquired(&udp, 0).increment(3);
+    pools::connections(&udp).set(4.0);
+
+    assert_eq!(tcp.primary.acquired.read(), 1);
+    assert_eq!(udp.primary.acquired.read(), 2);
+    assert_eq!(udp.replicas[0].acquired.read(), 3);
+    assert_eq!(udp.replicas[1].acquired.read(), 0);
+    assert_eq!(udp.find_gauge(pools::CONNECTIONS).unwrap().read(), 4.0);
+    assert_eq!(Udp::KIND, "udp");
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_generic_catalogue() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let catalogue = Pools::<Tcp>::new();
+    let mut s = String::new();
+    catalogue.render("", "", &mut s);
+    assert!(s.contains("pools_primary_acquired 0\n"));
+    assert!(s.contains("pools_replicas_acquired{index=\"1\"} 0\n"));
+}
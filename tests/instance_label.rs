@@ -0,0 +1,56 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Pools {
+    #[metric(instance_label = "pool")]
+    primary: Pool,
+    #[metric(instance_label = "pool", label(region = "eu"))]
+    replica: Pool,
+    other: Pool,
+}
+
+#[derive(Catalogue)]
+struct Pool {
+    /// Number of open connections
+    connections: Gauge,
+    queries: Counter,
+}
+
+#[test]
+fn instance_label_keeps_keys() {
+    let t = Pools::new();
+    t.primary.queries.increment(1);
+    t.replica.queries.increment(2);
+    assert_eq!(t.find_counter(pools::primary::QUERIES).unwrap().read(), 1);
+    assert_eq!(t.find_counter(pools::replica::QUERIES).unwrap().read(), 2);
+    assert!(t.find_gauge(pools::other::CONNECTIONS).is_some());
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_shared_family() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let t = Pools::new();
+    t.primary.queries.increment(1);
+    t.replica.queries.increment(2);
+    let mut s = String::new();
+    t.render("", "", &mut s);
+    assert!(s.contains(concat!(
+        "# HELP pools_pool_connections Number of open connections\n",
+        "# TYPE pools_pool_connections gauge\n",
+        "pools_pool_connections{pool=\"primary\"} 0\n",
+        "pools_pool_connections{region=\"eu\",pool=\"replica\"} 0\n",
+        "\n",
+    )));
+    assert!(s.contains(concat!(
+        "# TYPE pools_pool_queries counter\n",
+        "pools_pool_queries{pool=\"primary\"} 1\n",
+        "pools_pool_queries{region=\"eu\",pool=\"replica\"} 2\n",
+        "\n",
+    )));
+    assert_eq!(s.matches("# TYPE pools_pool_queries ").count(), 1);
+    assert!(s.contains("pools_other_queries 0\n"));
+    assert!(!s.contains("pools_primary_"));
+}
@@ -0,0 +1,88 @@
+use metrics_catalogue::*;
+use std::sync::atomic::{AtomicU64, Ordering};
+
+type Latency = Histogram<60>;
+
+/// Counter spread over several shards, to reduce contention
+struct ShardedCounter([AtomicU64; 4]);
+
+impl ConstDefault for ShardedCounter {
+    #[allow(clippy::declare_interior_mutable_const)]
+    const DEFAULT: Self = ShardedCounter([
+        AtomicU64::new(0),
+        AtomicU64::new(0),
+        AtomicU64::new(0),
+        AtomicU64::new(0),
+    ]);
+}
+
+impl CatalogueMetric for ShardedCounter {
+    const KIND: MetricKind = MetricKind::Counter;
+}
+
+impl CounterMetric for ShardedCounter {
+    fn increment(&self, val: u64) {
+        let shard = val as usize % self.0.len();
+        self.0[shard].fetch_add(val, Ordering::Relaxed);
+    }
+
+    fn read(&self) -> u64 {
+        self.0
+            .iter()
+            .map(|shard| shard.load(Ordering::Relaxed))
+            .sum()
+    }
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Service {
+    #[metric(kind = "histogram")]
+    latency: Latency,
+    #[metric(kind = "counter")]
+    requests: ShardedCounter,
+    #[metric(kind = "counter")]
+    shards: [ShardedCounter; 2],
+}
+
+static CATALOGUE: Service = Service::new();
+
+#[test]
+fn custom_metric_types() {
+    CATALOGUE.latency.insert(2.0);
+    assert_eq!(
+        CATALOGUE
+            .find_histogram(service::LATENCY)
+            .unwrap()
+            .read()
+            .len(),
+        1
+    );
+
+    CATALOGUE.increment_counter(&Key::from_name(service::REQUESTS), 3);
+    CATALOGUE.increment_counter(&Key::from_name(service::REQUESTS), 2);
+    assert_eq!(CATALOGUE.requests.read(), 5);
+    assert_eq!(CATALOGUE.find_counter(service::REQUESTS).unwrap().read(), 5);
+
+    CATALOGUE.increment_counter(&Key::from_name(service::SHARDS[1]), 1);
+    assert_eq!(CATALOGUE.shards[1].read(), 1);
+    assert_eq!(
+        <ShardedCounter as CatalogueMetric>::KIND,
+        MetricKind::Counter
+    );
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_custom_metric_types() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let catalogue = Service::new();
+    catalogue.requests.increment(4);
+    let mut s = String::new();
+    catalogue.render("", "", &mut s);
+    assert!(s.contains("# TYPE service_requests counter\n"));
+    assert!(s.contains("service_requests 4\n"));
+    assert!(s.contains("# TYPE service_latency histogram\n"));
+    assert!(s.contains("service_shards{index=\"1\"} 0\n"));
+}
@@ -0,0 +1,47 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Labelled {
+    #[metric(label(region = "eu"))]
+    requests: Counter,
+    plain: Counter,
+    #[metric(label(shard = "0"))]
+    shard: LabelledShard,
+}
+
+#[derive(Catalogue)]
+struct LabelledShard {
+    size: Gauge,
+    #[metric(label(kind = "read", quoted = "a\"b"))]
+    latency: Histogram<60>,
+}
+
+#[test]
+fn labels_do_not_change_keys() {
+    let t = Labelled::new();
+    assert!(t.find_counter(labelled::REQUESTS).is_some());
+    assert!(t.find_gauge(labelled::shard::SIZE).is_some());
+    assert!(t.find_histogram(labelled::shard::LATENCY).is_some());
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_labels() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let t = Labelled::new();
+    t.requests.increment(1);
+    t.shard.latency.insert(1.0);
+    let mut s = String::new();
+    t.render("", "", &mut s);
+    assert!(s.contains("labelled_requests{region=\"eu\"} 1\n"));
+    assert!(s.contains("labelled_plain 0\n"));
+    assert!(s.contains("labelled_shard_size{shard=\"0\"} 0\n"));
+    assert!(s.contains(
+        "labelled_shard_latency{shard=\"0\",kind=\"read\",quoted=\"a\\\"b\",quantile=\"0.5\"}"
+    ));
+    assert!(
+        s.contains("labelled_shard_latency_count{shard=\"0\",kind=\"read\",quoted=\"a\\\"b\"} 1\n")
+    );
+}
@@ -0,0 +1,106 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Server {
+    requests: Counter,
+    #[metric(skip)]
+    _hidden: Gauge,
+    latency: Histogram<60>,
+    responses: CounterFamily<Status>,
+    connections: [Gauge; 2],
+    workers: [Worker; 3],
+    queue: Queue,
+}
+
+#[derive(Catalogue)]
+struct Worker {
+    busy: DiscreteGauge,
+    jobs: Jobs,
+}
+
+#[derive(Catalogue)]
+struct Jobs {
+    done: Counter,
+}
+
+#[derive(Catalogue)]
+struct Queue(Jobs);
+
+#[derive(FamilyKey)]
+enum Status {
+    Ok,
+    NotFound,
+}
+
+/// Address of the data of a metric, regardless of its vtable
+fn address<T: ?Sized>(metric: &T) -> *const u8 {
+    metric as *const T as *const u8
+}
+
+#[test]
+fn locate_full_keys() {
+    let catalogue = Server::new();
+    let counters = [
+        (server::REQUESTS, &catalogue.requests),
+        (
+            server::responses::NOT_FOUND,
+            catalogue.responses.get(Status::NotFound),
+        ),
+        (server::responses::OK, catalogue.responses.get(Status::Ok)),
+        (
+            server::workers::jobs::DONE[2],
+            &catalogue.workers[2].jobs.done,
+        ),
+        (server::queue::DONE, &catalogue.queue.0.done),
+    ];
+    for (key, metric) in counters {
+        let found = catalogue.find_counter(key).unwrap();
+        assert_eq!(address(found), address(metric), "{}", key);
+    }
+
+    let gauge = catalogue.find_gauge(server::CONNECTIONS[1]).unwrap();
+    assert_eq!(address(gauge), address(&catalogue.connections[1]));
+    let busy = catalogue.find_gauge(server::workers::BUSY[1]).unwrap();
+    assert_eq!(address(busy), address(&catalogue.workers[1].busy));
+    let latency = catalogue.find_histogram(server::LATENCY).unwrap();
+    assert_eq!(address(latency), address(&catalogue.latency));
+
+    for descriptor in server::METRICS {
+        let found = match descriptor.kind {
+            MetricKind::Counter => catalogue.find_counter(descriptor.key).is_some(),
+            MetricKind::Gauge => catalogue.find_gauge(descriptor.key).is_some(),
+            MetricKind::Histogram => catalogue.find_histogram(descriptor.key).is_some(),
+        };
+        assert!(found, "{}", descriptor.key);
+    }
+}
+
+#[test]
+fn unknown_keys_and_kinds() {
+    let catalogue = Server::new();
+    assert!(catalogue.find_gauge(server::REQUESTS).is_none());
+    assert!(catalogue.find_counter(server::LATENCY).is_none());
+    assert!(catalogue.find_counter("server._hidden").is_none());
+    assert!(catalogue.find_counter("server.requests.total").is_none());
+    assert!(catalogue.find_gauge("server.connections.2").is_none());
+    assert!(catalogue
+        .find_counter("server.workers.3.jobs.done")
+        .is_none());
+}
+
+#[test]
+fn labelled_keys() {
+    let catalogue = Server::new();
+    let index = [Label::new("index", "1")];
+    catalogue
+        .route_counter("server.workers.jobs.done", &index)
+        .unwrap()
+        .increment(2);
+    catalogue
+        .route_gauge("server.connections", &index)
+        .unwrap()
+        .set(3.0);
+    assert_eq!(catalogue.workers[1].jobs.done.read(), 2);
+    assert_eq!(catalogue.connections[1].read(), 3.0);
+}
@@ -1,5 +1,6 @@
 use metrics_catalogue::{
-    Catalogue, Counter, DiscreteGauge, Gauge, Histogram, HistogramMetric, Key, Recorder,
+    Catalogue, Counter, DiscreteGauge, Gauge, GaugeMetric, Histogram, HistogramMetric, Key,
+    Recorder,
 };
 
 #[derive(Catalogue)]
@@ -152,3 +153,19 @@ fn histograms() {
         );
     }
 }
+
+#[test]
+fn concurrent_gauge_updates() {
+    let gauge = Gauge::new();
+    std::thread::scope(|s| {
+        for _ in 0..4 {
+            s.spawn(|| {
+                for _ in 0..1000 {
+                    gauge.increase(2.0);
+                    gauge.decrease(1.0);
+                }
+            });
+        }
+    });
+    assert_eq!(gauge.read(), 4000.0);
+}
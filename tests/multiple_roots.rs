@@ -0,0 +1,43 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+pub struct DataPlane {
+    requests: Counter,
+    shared: Shared,
+}
+
+#[derive(Catalogue)]
+#[metric(root, separator = "_")]
+pub struct Admin {
+    logins: Counter,
+    shared: Shared,
+}
+
+#[derive(Catalogue)]
+pub struct Shared {
+    errors: Counter,
+}
+
+#[test]
+fn independent_catalogues() {
+    assert_eq!(data_plane::REQUESTS, "data_plane.requests");
+    assert_eq!(data_plane::shared::ERRORS, "data_plane.shared.errors");
+    assert_eq!(admin::LOGINS, "admin_logins");
+    assert_eq!(admin::shared::ERRORS, "admin_shared_errors");
+}
+
+#[test]
+fn shared_scopes() {
+    let data_plane = DataPlane::new();
+    let admin = Admin::new();
+
+    data_plane.increment_counter(&Key::from_name(data_plane::shared::ERRORS), 1);
+    admin.increment_counter(&Key::from_name(admin::shared::ERRORS), 2);
+    assert_eq!(data_plane.shared.errors.read(), 1);
+    assert_eq!(admin.shared.errors.read(), 2);
+
+    // Keys of one catalogue don't resolve in the other
+    admin.increment_counter(&Key::from_name(data_plane::shared::ERRORS), 1);
+    assert_eq!(admin.shared.errors.read(), 2);
+}
@@ -0,0 +1,52 @@
+#![cfg(feature = "prometheus")]
+use metrics_catalogue::prometheus::StringRender;
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root, separator = "_")]
+struct Exporter {
+    #[metric("requests_total")]
+    requests: Counter,
+    _private: Gauge,
+    #[metric(instance_label = "shard")]
+    shard_a: Shard,
+    #[metric(instance_label = "shard")]
+    shard_b: Shard,
+}
+
+#[derive(Catalogue)]
+struct Shard {
+    latency: Histogram<60>,
+}
+
+/// Prometheus names should match `[a-zA-Z_:][a-zA-Z0-9_:]*`
+fn is_valid_prometheus_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_' || c == ':')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_' || c == ':')
+}
+
+#[test]
+fn rendered_names_are_valid() {
+    let catalogue = Exporter::new();
+    let mut s = String::new();
+    catalogue.render("", "", &mut s);
+    assert!(s.contains("exporter_requests_total 0\n"));
+    let names = s
+        .lines()
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let line = line
+                .strip_prefix("# TYPE ")
+                .or_else(|| line.strip_prefix("# HELP "))
+                .unwrap_or(line);
+            line.split([' ', '{']).next().unwrap()
+        })
+        .collect::<Vec<_>>();
+    assert!(!names.is_empty());
+    for name in names {
+        assert!(is_valid_prometheus_name(name), "{}", name);
+    }
+}
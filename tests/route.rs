@@ -0,0 +1,91 @@
+use metrics::{gauge, histogram, increment_counter};
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root, "db")]
+struct Sharded {
+    queries: Counter,
+    #[metric(route(shard = "a"))]
+    shard_a: Shard,
+    #[metric(route(shard = "b"))]
+    shard_b: Shard,
+}
+
+#[derive(Catalogue)]
+struct Shard {
+    queries: Counter,
+    size: Gauge,
+    latency: Histogram<60>,
+}
+
+static CATALOGUE: Sharded = Sharded::new();
+
+#[test]
+fn route_labelled_keys() {
+    metrics::set_recorder(&CATALOGUE).unwrap();
+
+    increment_counter!("db.queries", "shard" => "a");
+    increment_counter!("db.queries", "shard" => "a");
+    increment_counter!("db.queries", "shard" => "b");
+    increment_counter!("db.queries");
+    increment_counter!("db.queries", "shard" => "c");
+    gauge!("db.size", 4.0, "shard" => "b");
+    histogram!("db.latency", 1.0, "other" => "x", "shard" => "a");
+    increment_counter!("db.shard_b.queries");
+
+    assert_eq!(CATALOGUE.shard_a.queries.read(), 2);
+    assert_eq!(CATALOGUE.shard_b.queries.read(), 2);
+    assert_eq!(CATALOGUE.queries.read(), 2);
+    assert_eq!(CATALOGUE.shard_b.size.read(), 4.0);
+    assert_eq!(CATALOGUE.shard_a.latency.read(), vec![1.0]);
+
+    #[cfg(feature = "prometheus")]
+    {
+        use metrics_catalogue::prometheus::StringRender;
+
+        let mut s = String::new();
+        CATALOGUE.render("", "", &mut s);
+        assert!(s.contains("db_queries 2\n"));
+        assert!(s.contains(concat!(
+            "# TYPE db_size gauge\n",
+            "db_size{shard=\"a\"} 0\n",
+            "db_size{shard=\"b\"} 4\n",
+        )));
+    }
+}
+
+#[derive(Catalogue)]
+#[metric(root, "sharded_db", separator = "_")]
+struct Db {
+    queries: Counter,
+    #[metric(route(shard = "a"))]
+    shard_a: Shard,
+    #[metric(route(shard = "b"))]
+    shard_b: Shard,
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_routed_metrics_with_parent() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let catalogue = Db::new();
+    catalogue.queries.increment(1);
+    catalogue.shard_b.queries.increment(2);
+    let mut s = String::new();
+    catalogue.render("", "", &mut s);
+    // A single family of the parent metric & its routed counterparts
+    assert!(s.contains(concat!(
+        "# TYPE sharded_db_queries counter\n",
+        "sharded_db_queries 1\n",
+        "sharded_db_queries{shard=\"a\"} 0\n",
+        "sharded_db_queries{shard=\"b\"} 2\n",
+        "\n",
+    )));
+    assert_eq!(s.matches("# TYPE sharded_db_queries ").count(), 1);
+    assert!(s.contains(concat!(
+        "# TYPE sharded_db_size gauge\n",
+        "sharded_db_size{shard=\"a\"} 0\n",
+        "sharded_db_size{shard=\"b\"} 0\n",
+    )));
+}
@@ -19,3 +19,45 @@ fn custom_separator() {
         "custom_separator_foo-bar-counter"
     );
 }
+
+#[derive(Catalogue)]
+#[metric(root, separator = ":")]
+pub struct ColonSeparatorFoo {
+    bar: CustomSeparatorBar,
+}
+
+#[test]
+fn colon_separator() {
+    let catalogue = ColonSeparatorFoo::new();
+    assert_eq!(
+        colon_separator_foo::bar::COUNTER,
+        "colon_separator_foo:bar:counter"
+    );
+    assert!(catalogue
+        .find_counter(colon_separator_foo::bar::COUNTER)
+        .is_some());
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_invalid_separator() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    // Separators invalid in Prometheus names are rendered as `_`
+    let catalogue = CustomSeparatorFoo::new();
+    let mut output = String::new();
+    catalogue.render("", "", &mut output);
+    assert!(
+        output.contains("custom_separator_foo_bar_counter 0\n"),
+        "{}",
+        output
+    );
+    let catalogue = ColonSeparatorFoo::new();
+    let mut output = String::new();
+    catalogue.render("", "", &mut output);
+    assert!(
+        output.contains("colon_separator_foo:bar:counter 0\n"),
+        "{}",
+        output
+    );
+}
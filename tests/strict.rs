@@ -0,0 +1,77 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root, strict)]
+struct Server {
+    requests: Counter,
+    connections: [Gauge; 2],
+    #[metric(route(region = "eu"))]
+    eu: Region,
+}
+
+#[derive(Catalogue)]
+struct Region {
+    hits: Counter,
+}
+
+#[derive(Catalogue)]
+#[metric(root, strict)]
+struct Proxy {
+    requests: Counter,
+    unknown: DynamicRegistry<10>,
+}
+
+#[test]
+fn resolved_keys() {
+    let catalogue = Server::new();
+    catalogue.increment_counter(&Key::from_name(server::REQUESTS), 1);
+    catalogue.update_gauge(
+        &Key::from_name(server::CONNECTIONS[1]),
+        GaugeValue::Increment(1.0),
+    );
+    let routed = Key::from_parts("server.hits", vec![Label::new("region", "eu")]);
+    catalogue.increment_counter(&routed, 1);
+    // Registering a key records no sample
+    catalogue.register_counter(&Key::from_name("server.request"), None, None);
+    assert_eq!(catalogue.eu.hits.read(), 1);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Unknown key `server.request` recorded as a Counter")]
+fn unknown_key() {
+    let catalogue = Server::new();
+    catalogue.increment_counter(&Key::from_name("server.request"), 1);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Key `server.requests` of a Counter recorded as a Gauge")]
+fn kind_mismatch() {
+    let catalogue = Server::new();
+    catalogue.update_gauge(&Key::from_name(server::REQUESTS), GaugeValue::Absolute(1.0));
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Unknown key `server.hits` recorded as a Counter")]
+fn unrouted_key() {
+    let catalogue = Server::new();
+    let unrouted = Key::from_parts("server.hits", vec![Label::new("region", "us")]);
+    catalogue.increment_counter(&unrouted, 1);
+}
+
+#[test]
+fn dynamic_keys() {
+    let catalogue = Proxy::new();
+    catalogue.record_histogram(&Key::from_name("proxy.latency"), 1.0);
+    assert_eq!(catalogue.unknown.len(), 1);
+}
+
+#[test]
+#[cfg(debug_assertions)]
+#[should_panic(expected = "Key `proxy.requests` of a Counter recorded as a Histogram")]
+fn dynamic_kind_mismatch() {
+    let catalogue = Proxy::new();
+    catalogue.record_histogram(&Key::from_name(proxy::REQUESTS), 1.0);
+}
@@ -0,0 +1,68 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Service {
+    retries: RetryMetrics,
+    jobs: Jobs,
+}
+
+#[derive(Catalogue)]
+struct RetryMetrics(#[metric("attempts")] Counter, Histogram<60>);
+
+#[derive(Catalogue)]
+struct Jobs(JobMetrics);
+
+#[derive(Catalogue)]
+struct JobMetrics {
+    started: Counter,
+    running: Gauge,
+}
+
+static CATALOGUE: Service = Service::new();
+
+#[test]
+fn tuple_struct_keys() {
+    assert_eq!(service::retries::ATTEMPTS, "service.retries.attempts");
+    assert_eq!(service::retries::_1, "service.retries._1");
+
+    CATALOGUE.retries.0.increment(1);
+    CATALOGUE.retries.1.insert(2.0);
+    assert_eq!(
+        CATALOGUE
+            .find_counter(service::retries::ATTEMPTS)
+            .unwrap()
+            .read(),
+        1
+    );
+    assert!(CATALOGUE.find_histogram(service::retries::_1).is_some());
+    assert_eq!(service::retries::_1_(&CATALOGUE).read(), vec![2.0]);
+}
+
+#[test]
+fn transparent_newtype() {
+    assert_eq!(service::jobs::STARTED, "service.jobs.started");
+    assert_eq!(service::jobs::RUNNING, "service.jobs.running");
+
+    CATALOGUE.jobs.0.started.increment(3);
+    assert_eq!(
+        CATALOGUE
+            .find_counter(service::jobs::STARTED)
+            .unwrap()
+            .read(),
+        3
+    );
+    assert!(CATALOGUE.find_gauge(service::jobs::RUNNING).is_some());
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_tuple_struct() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let catalogue = Service::new();
+    let mut s = String::new();
+    catalogue.render("", "", &mut s);
+    assert!(s.contains("service_retries_attempts 0\n"));
+    assert!(s.contains("service_jobs_started 0\n"));
+}
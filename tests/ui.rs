@@ -0,0 +1,8 @@
+#[test]
+fn ui() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/*.rs");
+    // Naming rules of the exposition format
+    #[cfg(feature = "prometheus")]
+    t.compile_fail("tests/ui/prometheus/*.rs");
+}
@@ -0,0 +1,14 @@
+use metrics_catalogue::*;
+
+#[derive(FamilyKey)]
+enum Status {
+    Ok,
+    Error,
+}
+
+#[derive(Catalogue)]
+struct Metrics {
+    responses: [CounterFamily<Status>; 2],
+}
+
+fn main() {}
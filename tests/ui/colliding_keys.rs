@@ -0,0 +1,15 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root, separator = "_")]
+struct Service {
+    a_b: Counter,
+    a: Sub,
+}
+
+#[derive(Catalogue)]
+struct Sub {
+    b: Counter,
+}
+
+fn main() {}
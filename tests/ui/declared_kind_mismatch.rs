@@ -0,0 +1,49 @@
+use metrics_catalogue::*;
+
+/// Counter of connections, also exposed as the number of open connections
+struct Connections(Gauge);
+
+impl ConstDefault for Connections {
+    const DEFAULT: Self = Connections(Gauge::new());
+}
+
+impl CatalogueMetric for Connections {
+    const KIND: MetricKind = MetricKind::Counter;
+}
+
+impl CounterMetric for Connections {
+    fn increment(&self, val: u64) {
+        self.0.increase(val as f64)
+    }
+
+    fn read(&self) -> u64 {
+        GaugeMetric::read(&self.0) as u64
+    }
+}
+
+impl GaugeMetric for Connections {
+    fn set(&self, val: f64) {
+        self.0.set(val)
+    }
+
+    fn increase(&self, val: f64) {
+        self.0.increase(val)
+    }
+
+    fn decrease(&self, val: f64) {
+        self.0.decrease(val)
+    }
+
+    fn read(&self) -> f64 {
+        GaugeMetric::read(&self.0)
+    }
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Metrics {
+    #[metric(kind = "gauge")]
+    connections: Connections,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(description = 1)]
+    requests: Counter,
+}
+
+fn main() {}
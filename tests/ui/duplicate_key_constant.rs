@@ -0,0 +1,10 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    requests: Counter,
+    #[metric("REQUESTS")]
+    total_requests: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    my_b: Counter,
+    #[metric("my_b")]
+    other: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Metrics {
+    requests: Counter,
+    misses: UnknownKeys,
+    other_misses: UnknownKeys,
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use metrics_catalogue::*;
+
+#[derive(FamilyKey)]
+enum Status {}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+enum Metrics {
+    Requests(Counter),
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use metrics_catalogue::*;
+
+#[derive(FamilyKey)]
+struct Status {
+    code: u16,
+}
+
+fn main() {}
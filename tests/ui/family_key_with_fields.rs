@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(FamilyKey)]
+enum Status {
+    Ok,
+    Error(u16),
+}
+
+fn main() {}
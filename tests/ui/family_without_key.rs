@@ -0,0 +1,8 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    responses: CounterFamily,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(FamilyKey)]
+enum Status<T> {
+    Ok,
+    Error(T),
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(index_label = "worker")]
+    requests: Counter,
+}
+
+fn main() {}
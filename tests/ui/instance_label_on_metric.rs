@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(instance_label = "shard")]
+    requests: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Pools {
+    #[metric(instance_label = "pool")]
+    primary: Pool,
+    #[metric(instance_label = "pool")]
+    replica: Replica,
+}
+
+#[derive(Catalogue)]
+struct Pool {
+    queries: Counter,
+}
+
+#[derive(Catalogue)]
+struct Replica {
+    queries: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric("my-requests")]
+    requests: Counter,
+}
+
+fn main() {}
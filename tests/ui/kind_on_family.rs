@@ -0,0 +1,15 @@
+use metrics_catalogue::*;
+
+#[derive(FamilyKey)]
+enum Status {
+    Ok,
+    Error,
+}
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(kind = "gauge")]
+    responses: CounterFamily<Status>,
+}
+
+fn main() {}
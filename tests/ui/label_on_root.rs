@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root, label(region = "eu"))]
+struct Metrics {
+    requests: Counter,
+}
+
+fn main() {}
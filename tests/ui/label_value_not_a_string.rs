@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(label(status = 200))]
+    requests: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use metrics_catalogue::*;
+
+const WORKERS: usize = 4;
+
+#[derive(Catalogue)]
+struct Metrics {
+    requests: [Counter; WORKERS],
+}
+
+fn main() {}
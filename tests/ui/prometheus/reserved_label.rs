@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(label(__name = "requests"))]
+    requests: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(route(shard = "a"))]
+    requests: Counter,
+}
+
+fn main() {}
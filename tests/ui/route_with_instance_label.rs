@@ -0,0 +1,14 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Shard {
+    queries: Counter,
+}
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(route(shard = "a"), instance_label = "shard")]
+    shard_a: Shard,
+}
+
+fn main() {}
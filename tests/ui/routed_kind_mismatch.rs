@@ -0,0 +1,16 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Db {
+    queries: Gauge,
+    #[metric(route(shard = "a"))]
+    shard_a: Shard,
+}
+
+#[derive(Catalogue)]
+struct Shard {
+    queries: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,22 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Db {
+    #[metric(route(shard = "a"))]
+    shard_a: Shard,
+    #[metric(route(shard = "b"))]
+    shard_b: Replica,
+}
+
+#[derive(Catalogue)]
+struct Shard {
+    queries: Counter,
+}
+
+#[derive(Catalogue)]
+struct Replica {
+    queries: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(separator = "_")]
+struct Metrics {
+    requests: Counter,
+}
+
+fn main() {}
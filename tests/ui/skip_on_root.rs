@@ -0,0 +1,10 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+#[metric(skip)]
+struct Metrics {
+    requests: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,10 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    requests: Counter,
+    #[metric(skip)]
+    _tags: (u8, u8),
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use metrics_catalogue::*;
+
+type Latency = Histogram<60>;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Metrics {
+    requests: Counter,
+    latency: Latency,
+}
+
+fn main() {}
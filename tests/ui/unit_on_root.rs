@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root, unit = "seconds")]
+struct Metrics {
+    uptime: Gauge,
+}
+
+fn main() {}
@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    requests: Counter,
+    misses: UnknownKeys,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Metrics {
+    requests: Counter,
+    unknown: DynamicRegistry<100>,
+    misses: UnknownKeys,
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use metrics_catalogue::*;
+
+type Requests = Counter;
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(kind = "summary")]
+    requests: Requests,
+}
+
+fn main() {}
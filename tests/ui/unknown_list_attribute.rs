@@ -0,0 +1,9 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    #[metric(labels(method = "get"))]
+    requests: Counter,
+}
+
+fn main() {}
@@ -0,0 +1,8 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+struct Metrics {
+    requests: [Counter; 0],
+}
+
+fn main() {}
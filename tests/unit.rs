@@ -0,0 +1,76 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Units {
+    #[metric(unit = "seconds")]
+    latency: Histogram<60>,
+    #[metric(unit = "bytes")]
+    memory: Gauge,
+    #[metric(unit = "count_per_second")]
+    rate: DiscreteGauge,
+    requests: Counter,
+    #[metric(unit = "bytes")]
+    sent_bytes: Counter,
+    sub: UnitsSub,
+}
+
+#[derive(Catalogue)]
+struct UnitsSub {
+    #[metric(unit = "milliseconds")]
+    nested: Counter,
+}
+
+#[test]
+fn registry_units() {
+    let t = Units::new();
+    let known_units = [
+        (units::LATENCY, Some(Unit::Seconds)),
+        (units::MEMORY, Some(Unit::Bytes)),
+        (units::RATE, Some(Unit::CountPerSecond)),
+        (units::REQUESTS, None),
+        (units::SENT_BYTES, Some(Unit::Bytes)),
+        (units::sub::NESTED, Some(Unit::Milliseconds)),
+        ("units.unknown", None),
+    ];
+    for (key, unit) in known_units {
+        assert_eq!(t.find_unit(key), unit, "key {}", key);
+    }
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn unit_lines() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let t = Units::new();
+    let mut s = String::new();
+    t.render("", "", &mut s);
+    assert!(s.contains("# UNIT units_latency_seconds seconds\n"));
+    assert!(s.contains("# UNIT units_memory_bytes bytes\n"));
+    assert!(s.contains("# UNIT units_rate_count_per_second count_per_second\n"));
+    assert!(s.contains("# UNIT units_sub_nested_milliseconds milliseconds\n"));
+    assert!(!s.contains("# UNIT units_requests"));
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn unit_suffixed_names() {
+    use metrics_catalogue::prometheus::StringRender;
+
+    let t = Units::new();
+    t.memory.set(2.0);
+    let mut s = String::new();
+    t.render("", "", &mut s);
+    // The name of a family with a unit ends with the unit, as required by its `# UNIT` line
+    assert!(s.contains(concat!(
+        "# UNIT units_memory_bytes bytes\n",
+        "# TYPE units_memory_bytes gauge\n",
+        "units_memory_bytes 2\n",
+    )));
+    assert!(s.contains("units_latency_seconds_count 0\n"));
+    // Names already ending with their unit are kept
+    assert!(s.contains("# UNIT units_sent_bytes bytes\n"));
+    assert!(!s.contains("units_sent_bytes_bytes"));
+    assert!(s.contains("units_requests 0\n"));
+}
@@ -0,0 +1,113 @@
+#[cfg(feature = "prometheus")]
+use metrics_catalogue::prometheus::StringRender;
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Server {
+    requests: Counter,
+    connections: Gauge,
+    latency: Histogram<60>,
+    misses: UnknownKeys,
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Client {
+    requests: Counter,
+    misses: UnknownKeys,
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Proxy {
+    requests: Counter,
+    unknown: DynamicRegistry<10>,
+}
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Worker {
+    jobs: Counter,
+}
+
+#[test]
+fn count_unknown_keys() {
+    let catalogue = Server::new();
+    let unknown_keys = catalogue.unknown_keys().unwrap();
+    catalogue.increment_counter(&Key::from_name(server::REQUESTS), 1);
+    catalogue.update_gauge(
+        &Key::from_name(server::CONNECTIONS),
+        GaugeValue::Absolute(1.0),
+    );
+    catalogue.record_histogram(&Key::from_name(server::LATENCY), 1.0);
+    assert!(unknown_keys.is_empty());
+
+    catalogue.increment_counter(&Key::from_name("server.request"), 1);
+    catalogue.increment_counter(&Key::from_name("server.request"), 1);
+    // Known keys recorded as another kind are unknown to the catalogue
+    catalogue.record_histogram(&Key::from_name(server::REQUESTS), 1.0);
+    // Registering a key records no sample
+    catalogue.register_gauge(&Key::from_name("server.connection"), None, None);
+    assert_eq!(unknown_keys.get("server.request"), 2);
+    assert_eq!(unknown_keys.get(server::REQUESTS), 1);
+    assert_eq!(unknown_keys.get("server.connection"), 0);
+    assert_eq!(unknown_keys.total(), 3);
+    assert_eq!(catalogue.requests.read(), 1);
+
+    // Keys beyond the capacity are counted under an empty key
+    for index in 0..UnknownKeys::CAPACITY {
+        catalogue.update_gauge(
+            &Key::from_name(format!("server.gauge_{}", index)),
+            GaugeValue::Increment(1.0),
+        );
+    }
+    assert_eq!(unknown_keys.get(""), 2);
+    assert_eq!(unknown_keys.total(), 3 + UnknownKeys::CAPACITY as u64);
+    assert_eq!(catalogue.misses.total(), unknown_keys.total());
+}
+
+#[test]
+fn count_unknown_keys_per_instance() {
+    let (first, second) = (Server::new(), Server::new());
+    first.increment_counter(&Key::from_name("server.request"), 1);
+    assert_eq!(first.unknown_keys().unwrap().get("server.request"), 1);
+    assert!(second.unknown_keys().unwrap().is_empty());
+}
+
+#[test]
+fn drop_unknown_keys_without_counting() {
+    let catalogue = Worker::new();
+    catalogue.increment_counter(&Key::from_name("worker.job"), 1);
+    assert!(catalogue.unknown_keys().is_none());
+}
+
+#[test]
+fn forward_unknown_keys() {
+    let catalogue = Proxy::new();
+    catalogue.increment_counter(&Key::from_name("proxy.request"), 1);
+    assert!(catalogue.unknown_keys().is_none());
+    assert_eq!(catalogue.unknown.len(), 1);
+}
+
+#[cfg(feature = "prometheus")]
+#[test]
+fn render_unknown_keys() {
+    let catalogue = Client::new();
+    let mut output = String::new();
+    catalogue.render("", "", &mut output);
+    assert!(!output.contains("catalogue_unknown_key_total"));
+
+    catalogue.increment_counter(&Key::from_name("client.request"), 2);
+    catalogue.increment_counter(&Key::from_name("client.\"quoted\""), 1);
+    let mut output = String::new();
+    catalogue.render("", "", &mut output);
+    let lines = output.lines().collect::<Vec<_>>();
+    for line in [
+        "# TYPE catalogue_unknown_key_total counter",
+        "catalogue_unknown_key_total{key=\"client.request\"} 1",
+        "catalogue_unknown_key_total{key=\"client.\\\"quoted\\\"\"} 1",
+    ] {
+        assert!(lines.contains(&line), "No {} in output", line);
+    }
+}
@@ -0,0 +1,86 @@
+use metrics_catalogue::*;
+
+#[derive(Catalogue)]
+#[metric(root)]
+struct Server {
+    requests: Counter,
+    latency: Histogram<60>,
+    #[metric(skip)]
+    _hidden: Gauge,
+    responses: CounterFamily<Status>,
+    connections: [Gauge; 2],
+    workers: [Worker; 2],
+    primary: Shard,
+}
+
+#[derive(Catalogue)]
+struct Worker {
+    busy: DiscreteGauge,
+}
+
+#[derive(Catalogue)]
+struct Shard {
+    size: Gauge,
+}
+
+#[derive(FamilyKey)]
+enum Status {
+    Ok,
+    NotFound,
+}
+
+#[test]
+fn visit_all_metrics() {
+    let catalogue = Server::new();
+    let mut visited = Vec::new();
+    catalogue.visit(&mut |key: &str, metric: MetricRef<'_>| {
+        visited.push((key.to_string(), metric.kind()));
+    });
+    assert_eq!(
+        visited,
+        [
+            (server::REQUESTS, MetricKind::Counter),
+            (server::LATENCY, MetricKind::Histogram),
+            (server::responses::OK, MetricKind::Counter),
+            (server::responses::NOT_FOUND, MetricKind::Counter),
+            (server::CONNECTIONS[0], MetricKind::Gauge),
+            (server::CONNECTIONS[1], MetricKind::Gauge),
+            (server::primary::SIZE, MetricKind::Gauge),
+            (server::workers::BUSY[0], MetricKind::Gauge),
+            (server::workers::BUSY[1], MetricKind::Gauge),
+        ]
+        .map(|(key, kind)| (key.to_string(), kind))
+    );
+}
+
+/// Exporter summing all counters & gauges, outside of the provided renderers
+#[derive(Default)]
+struct Totals {
+    counters: u64,
+    gauges: f64,
+}
+
+impl MetricVisitor for Totals {
+    fn visit(&mut self, _key: &str, metric: MetricRef<'_>) {
+        match metric {
+            MetricRef::Counter(counter) => self.counters += counter.read(),
+            MetricRef::Gauge(gauge) => self.gauges += gauge.read(),
+            MetricRef::Histogram(_) => {}
+        }
+    }
+}
+
+#[test]
+fn visit_metric_values() {
+    let catalogue = Server::new();
+    catalogue.requests.increment(3);
+    catalogue.responses.get(Status::NotFound).increment(2);
+    catalogue.responses.get(Status::Ok).increment(1);
+    catalogue.connections[1].set(4.0);
+    catalogue.workers[0].busy.increase(1.0);
+
+    let mut totals = Totals::default();
+    catalogue.visit(&mut totals);
+    assert_eq!(totals.counters, 6);
+    assert_eq!(totals.gauges, 5.0);
+}